@@ -110,9 +110,32 @@ dfx deploy agent_canister --argument '(record {
 
 
 
-dfx deploy NeuroPad_backend --argument "(record { payment_recipient = principal \"${RECIEVER}\"; ic_asset_canister_id = principal \"${ASSET_CANISTER_ID}\"; agent_canister_id = principal \"${AGENT_CANISTER_ID}\"; neuropad_ledger_id = principal \"${NEUROPAD_TOKEN}\"; })"
+dfx deploy NeuroPad_backend --argument "(record { payment_recipient = principal \"${RECIEVER}\"; ic_asset_canister_id = principal \"${ASSET_CANISTER_ID}\"; agent_canister_id = principal \"${AGENT_CANISTER_ID}\"; neuropad_ledger_id = principal \"${NEUROPAD_TOKEN}\"; launch_fee = 0; })"
+
+# the backend keeps the agent and ledger wasms in its own memory, they are uploaded in chunks
+upload_wasm() {
+    KIND=$1
+    WASM=$2
+    TMP=$(mktemp -d)
+
+    dfx canister call NeuroPad_backend cancel_wasm_upload "(variant { ${KIND} })"
+    split -b 1000000 "${WASM}" "${TMP}/chunk_"
+    for CHUNK in "${TMP}"/chunk_*; do
+        echo "(variant { ${KIND} }, blob \"$(xxd -p "${CHUNK}" | tr -d '\n' | sed 's/../\\&/g')\")" > "${TMP}/arg"
+        dfx canister call NeuroPad_backend upload_wasm_chunk --argument-file "${TMP}/arg"
+    done
+
+    HASH=$(sha256sum "${WASM}" | cut -d' ' -f1 | sed 's/../\\&/g')
+    dfx canister call NeuroPad_backend commit_wasm_upload "(variant { ${KIND} }, blob \"${HASH}\")"
+    rm -rf "${TMP}"
+}
+
+upload_wasm Agent .dfx/local/canisters/agent_canister/agent_canister.wasm
+upload_wasm Ledger .dfx/local/canisters/icrc1_ledger_canister/icrc1_ledger_canister.wasm.gz
 
  dfx deploy ic_asset_handler
+# the backend uploads agent images without an access token, so it has to be a manager
+dfx canister call ic_asset_handler admin_set_managers "(vec { principal \"${NEUROPAD_BACKEND_ID}\" })"
  
  dfx deploy internet_identity
  
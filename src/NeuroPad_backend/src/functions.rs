@@ -2,7 +2,18 @@ mod user_route;
 pub use user_route::*;
 
 mod post_route;
+pub use post_route::rearm_pending_launches;
+mod proposal_route;
+mod launch_route;
+pub use launch_route::*;
+mod upgrade_route;
+pub use upgrade_route::rearm_agent_upgrades;
+mod wasm_route;
+mod cycles_route;
+pub use cycles_route::schedule_cycles_check;
+mod genesis_route;
 mod ledger_functions;
+pub use ledger_functions::*;
 
 mod canister_factory;
 pub use canister_factory::*;
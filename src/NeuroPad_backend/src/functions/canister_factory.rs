@@ -3,11 +3,14 @@
 use crate::api::call::{call_with_payment128, CallResult};
 use crate::api::canister_version;
 use crate::types::{
-    CanisterIdRecord, CreateCanisterArgument, CreateCanisterArgumentExtended,
-    InstallCodeArgument, InstallCodeArgumentExtended,
+    CanisterIdRecord, CanisterInstallMode, CanisterStatusResponse, ChunkHash,
+    ClearChunkStoreArgument, CreateCanisterArgument, CreateCanisterArgumentExtended,
+    InstallChunkedCodeArgument, InstallChunkedCodeArgumentExtended, InstallCodeArgument,
+    InstallCodeArgumentExtended, UploadChunkArgument,
 };
 
 use candid::Principal;
+use sha2::{Digest, Sha256};
 
 pub async fn create_new_canister(
     arg: CreateCanisterArgument, // cycles: u128,
@@ -57,4 +60,116 @@ pub async fn install_code_in_canister(
         cycles,
     )
     .await
-}
\ No newline at end of file
+}
+
+pub async fn upload_chunk_to_canister(arg: UploadChunkArgument) -> CallResult<(ChunkHash,)> {
+    ic_cdk::call(Principal::management_canister(), "upload_chunk", (arg,)).await
+}
+
+pub async fn clear_chunk_store_of_canister(arg: ClearChunkStoreArgument) -> CallResult<()> {
+    ic_cdk::call(Principal::management_canister(), "clear_chunk_store", (arg,)).await
+}
+
+pub async fn install_chunked_code_in_canister(arg: InstallChunkedCodeArgument) -> CallResult<()> {
+    let cycles: u128 = 1_000_000_000_000;
+
+    let extended_arg = InstallChunkedCodeArgumentExtended {
+        mode: arg.mode,
+        target_canister: arg.target_canister,
+        store_canister: arg.store_canister,
+        chunk_hashes_list: arg.chunk_hashes_list,
+        wasm_module_hash: arg.wasm_module_hash,
+        arg: arg.arg,
+        sender_canister_version: Some(canister_version()),
+    };
+
+    call_with_payment128(
+        Principal::management_canister(),
+        "install_chunked_code",
+        (extended_arg,),
+        cycles,
+    )
+    .await
+}
+
+pub fn wasm_module_hash(wasm_module: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(wasm_module);
+    hasher.finalize().to_vec()
+}
+
+// small modules go through a single install_code call, when the module and its init arg
+// would not fit in one message the module is uploaded to the chunk store of the target
+// canister first
+pub async fn install_wasm(
+    mode: CanisterInstallMode,
+    canister_id: Principal,
+    wasm_module: Vec<u8>,
+    arg: Vec<u8>,
+) -> Result<(), String> {
+    if wasm_module.len().saturating_add(arg.len()) <= crate::utils::MAX_INSTALL_CODE_SIZE {
+        let install_arg = InstallCodeArgument {
+            mode,
+            canister_id,
+            wasm_module: wasm_module.clone(),
+            arg,
+        };
+
+        return install_code_in_canister(install_arg, wasm_module)
+            .await
+            .map_err(|(_, err_string)| err_string);
+    }
+
+    // chunks left behind by an earlier attempt would only waste the store
+    clear_chunk_store_of_canister(ClearChunkStoreArgument { canister_id })
+        .await
+        .map_err(|(_, err_string)| format!("Failed to clear chunk store: {}", err_string))?;
+
+    let mut chunk_hashes_list = Vec::new();
+    for chunk in wasm_module.chunks(crate::utils::WASM_CHUNK_SIZE) {
+        let (chunk_hash,) = upload_chunk_to_canister(UploadChunkArgument {
+            canister_id,
+            chunk: chunk.to_vec(),
+        })
+        .await
+        .map_err(|(_, err_string)| format!("Failed to upload wasm chunk: {}", err_string))?;
+        chunk_hashes_list.push(chunk_hash);
+    }
+
+    let install_arg = InstallChunkedCodeArgument {
+        mode,
+        target_canister: canister_id,
+        store_canister: None,
+        chunk_hashes_list,
+        wasm_module_hash: wasm_module_hash(&wasm_module),
+        arg,
+    };
+
+    install_chunked_code_in_canister(install_arg)
+        .await
+        .map_err(|(_, err_string)| err_string)?;
+
+    if let Err((_, err_string)) =
+        clear_chunk_store_of_canister(ClearChunkStoreArgument { canister_id }).await
+    {
+        ic_cdk::println!("Failed to clear chunk store of {}: {}", canister_id, err_string);
+    }
+
+    Ok(())
+}
+
+pub async fn stop_canister_by_id(arg: CanisterIdRecord) -> CallResult<()> {
+    ic_cdk::call(Principal::management_canister(), "stop_canister", (arg,)).await
+}
+
+pub async fn start_canister_by_id(arg: CanisterIdRecord) -> CallResult<()> {
+    ic_cdk::call(Principal::management_canister(), "start_canister", (arg,)).await
+}
+
+pub async fn delete_canister_by_id(arg: CanisterIdRecord) -> CallResult<()> {
+    ic_cdk::call(Principal::management_canister(), "delete_canister", (arg,)).await
+}
+
+pub async fn canister_status_of(arg: CanisterIdRecord) -> CallResult<(CanisterStatusResponse,)> {
+    ic_cdk::call(Principal::management_canister(), "canister_status", (arg,)).await
+}
@@ -0,0 +1,106 @@
+use crate::guards::*;
+use crate::routes::{agent_cycles_report, monitored_canisters, needs_top_up, record_cycles_check};
+use crate::{with_state, AgentCyclesReport, CanisterCycles, CanisterIdRecord, CyclesConfig};
+use candid::Principal;
+use ic_cdk::{query, update};
+use ic_cdk_timers::set_timer;
+use std::time::Duration;
+
+use super::canister_factory::{canister_status_of, deposit_cycles_in_canister};
+
+#[update(guard = is_controller)]
+fn set_cycles_config(config: CyclesConfig) -> Result<(), String> {
+    if config.check_interval_seconds == 0 {
+        return Err(String::from("Cycles check interval must be at least one second."));
+    }
+    if config.top_up_amount == 0 {
+        return Err(String::from("Top-up amount must be greater than zero."));
+    }
+
+    with_state(|state| state.cycles_config.set(config))
+        .map(|_| ())
+        .map_err(|err| format!("Failed to save cycles config: {:?}", err))
+}
+
+#[query(guard = is_controller)]
+fn get_cycles_config() -> CyclesConfig {
+    with_state(|state| state.cycles_config.get().clone())
+}
+
+// cycles of the agent canister and its ledger as of the last check
+#[query(guard = prevent_anonymous)]
+fn get_agent_cycles(agent_canister_id: Principal) -> Result<AgentCyclesReport, String> {
+    with_state(|state| agent_cycles_report(state, agent_canister_id))
+}
+
+#[query(guard = is_controller)]
+fn list_canister_cycles() -> Vec<CanisterCycles> {
+    with_state(|state| state.canister_cycles.iter().map(|(_, record)| record).collect())
+}
+
+// checks right away instead of waiting for the timer, returns how many canisters got topped up
+#[update(guard = is_controller)]
+async fn check_canister_cycles() -> u32 {
+    check_all_canisters().await
+}
+
+// the next check is scheduled with the interval configured at that time
+pub fn schedule_cycles_check() {
+    let interval = with_state(|state| state.cycles_config.get().check_interval_seconds);
+
+    set_timer(Duration::from_secs(interval), || {
+        ic_cdk::spawn(async {
+            check_all_canisters().await;
+            schedule_cycles_check();
+        });
+    });
+}
+
+async fn check_all_canisters() -> u32 {
+    let canisters = with_state(|state| monitored_canisters(state));
+    let mut topped_up_count = 0;
+
+    for canister in canisters {
+        let (canister_id, _, _) = canister;
+
+        let status = canister_status_of(CanisterIdRecord { canister_id })
+            .await
+            .map(|(status,)| status)
+            .map_err(|(_, err)| format!("Failed to read canister status: {}", err));
+
+        let topped_up = match &status {
+            Ok(status) if with_state(|state| needs_top_up(state, status)) => {
+                top_up_canister(canister_id).await
+            }
+            _ => Ok(0),
+        };
+
+        match &topped_up {
+            Ok(0) => {}
+            Ok(_) => topped_up_count += 1,
+            Err(err) => ic_cdk::println!("Failed to top up {}: {}", canister_id, err),
+        }
+
+        with_state(|state| {
+            record_cycles_check(state, canister, status, topped_up, ic_cdk::api::time())
+        });
+    }
+
+    topped_up_count
+}
+
+// deposits the configured amount, keeping the reserve the backend needs for launches
+async fn top_up_canister(canister_id: Principal) -> Result<u64, String> {
+    let config = with_state(|state| state.cycles_config.get().clone());
+
+    let balance = ic_cdk::api::canister_balance128();
+    if balance < (config.top_up_amount as u128).saturating_add(config.backend_reserve as u128) {
+        return Err(String::from("Backend cycles balance is below its reserve."));
+    }
+
+    deposit_cycles_in_canister(CanisterIdRecord { canister_id }, config.top_up_amount as u128)
+        .await
+        .map_err(|(_, err)| format!("Failed to deposit cycles: {}", err))?;
+
+    Ok(config.top_up_amount)
+}
@@ -0,0 +1,136 @@
+use crate::guards::*;
+use crate::routes::{
+    check_genesis_window, claim_contributions, contribution_key, finish_escrow, genesis_summary,
+    record_contribution,
+};
+use crate::{with_state, GenesisContribution, GenesisSummary, LaunchPaymentStatus};
+use candid::{Nat, Principal};
+use ic_cdk::{query, update};
+
+use super::post_route::{send_escrowed_tokens, transfer};
+
+// commits NeuroPad tokens to a genesis launch through an ICRC-2 approval,
+// returns the total contribution of the caller
+#[update(guard = prevent_anonymous)]
+async fn contribute_to_genesis(launch_id: u64, amount: Nat) -> Result<Nat, String> {
+    let contributor = ic_cdk::api::caller();
+
+    if amount == Nat::from(0u64) {
+        return Err(String::from("Contribution has to be greater than zero."));
+    }
+    with_state(|state| check_genesis_window(state, launch_id, ic_cdk::api::time()))?;
+
+    transfer(amount.clone(), contributor)
+        .await
+        .map_err(|err| format!("Failed to collect the contribution: {}", err))?;
+
+    // the window can close while the transfer is in flight
+    let window = with_state(|state| check_genesis_window(state, launch_id, ic_cdk::api::time()));
+    if let Err(err) = window {
+        let canister_meta_data = with_state(|state| state.canister_data.get(&0))
+            .ok_or(String::from(crate::utils::CANISTER_DATA_NOT_FOUND))?;
+        send_escrowed_tokens(
+            canister_meta_data.neuropad_ledger_id,
+            contributor,
+            amount,
+            launch_id,
+            None,
+        )
+        .await??;
+        return Err(err);
+    }
+
+    Ok(with_state(|state| {
+        record_contribution(state, launch_id, contributor, amount, ic_cdk::api::time())
+    }))
+}
+
+#[query(guard = prevent_anonymous)]
+fn get_genesis_summary(launch_id: u64) -> Result<GenesisSummary, String> {
+    with_state(|state| genesis_summary(state, launch_id, ic_cdk::api::time()))
+}
+
+#[query(guard = prevent_anonymous)]
+fn get_genesis_contributions(launch_id: u64) -> Vec<GenesisContribution> {
+    with_state(|state| {
+        state
+            .genesis_contributions
+            .iter()
+            .map(|(_, contribution)| contribution)
+            .filter(|contribution| contribution.launch_id == launch_id)
+            .collect()
+    })
+}
+
+#[query(guard = prevent_anonymous)]
+fn get_my_genesis_contributions() -> Vec<GenesisContribution> {
+    let caller = ic_cdk::api::caller();
+
+    with_state(|state| {
+        state
+            .genesis_contributions
+            .iter()
+            .map(|(_, contribution)| contribution)
+            .filter(|contribution| contribution.contributor == caller)
+            .collect()
+    })
+}
+
+// moves the raised tokens to the agent canister once it is live, or refunds every
+// contributor when the launch was cancelled or rolled back
+pub async fn settle_genesis_contributions(
+    launch_id: u64,
+    agent_canister_id: Option<Principal>,
+) -> Result<(), String> {
+    let canister_meta_data = with_state(|state| state.canister_data.get(&0))
+        .ok_or(String::from(crate::utils::CANISTER_DATA_NOT_FOUND))?;
+
+    // claimed before the transfers so a concurrent settlement does not send them again
+    let contributions =
+        with_state(|state| claim_contributions(state, launch_id, ic_cdk::api::time()));
+
+    let mut result = Ok(());
+
+    for (contribution, created_at_time) in contributions {
+        let (to, settled_status) = match agent_canister_id {
+            Some(agent_canister_id) => (agent_canister_id, LaunchPaymentStatus::Forwarded),
+            None => (contribution.contributor, LaunchPaymentStatus::Refunded),
+        };
+
+        let sent = send_escrowed_tokens(
+            canister_meta_data.neuropad_ledger_id,
+            to,
+            contribution.amount.clone(),
+            launch_id,
+            Some(created_at_time),
+        )
+        .await;
+
+        let key = contribution_key(launch_id, &contribution.contributor);
+        with_state(|state| {
+            if let Some(mut contribution) = state.genesis_contributions.get(&key) {
+                finish_escrow(
+                    &mut contribution.contribution_status,
+                    &mut contribution.settlement_created_at,
+                    &mut contribution.settled_block_index,
+                    &sent,
+                    settled_status,
+                );
+                state.genesis_contributions.insert(key, contribution);
+            }
+        });
+
+        // one failed transfer should not hold back the other contributors
+        if let Err(err) = sent.and_then(|sent| sent) {
+            ic_cdk::println!(
+                "Failed to settle contribution of {} to launch {}: {}",
+                contribution.contributor,
+                launch_id,
+                err
+            );
+            result = Err(err);
+        }
+    }
+
+    result
+}
@@ -0,0 +1,355 @@
+use crate::guards::*;
+use crate::routes::{
+    checkpoint_launch, completed_launch_of, create_empty_canister, deposit_cycles,
+    finish_launch_controller, genesis_shares, install_agent_code, install_agent_ledger,
+    resume_launch_controller, stuck_launches, upload_agent_image,
+};
+use crate::{with_state, AgentDetails, CanisterIdRecord, LaunchRecord, LaunchStatus, LaunchStep};
+use candid::Principal;
+use ic_cdk::{query, update};
+
+use super::canister_functions::call_inter_canister;
+use super::canister_factory::{delete_canister_by_id, stop_canister_by_id};
+use super::genesis_route::settle_genesis_contributions;
+use super::post_route::settle_launch_payment;
+
+fn checkpoint(
+    launch_id: u64,
+    step: LaunchStep,
+    update: impl FnOnce(&mut LaunchRecord),
+) -> Result<LaunchRecord, String> {
+    with_state(|state| checkpoint_launch(state, launch_id, step, ic_cdk::api::time(), update))
+}
+
+// runs the remaining steps of a launch and records how it ended,
+// the launch fee is only released to the platform once the agent is live
+// and refunded with the genesis contributions once the launch failed for the last time
+pub async fn run_launch(launch_id: u64) -> Result<String, String> {
+    let result = run_launch_steps(launch_id).await;
+
+    let record = with_state(|state| {
+        finish_launch_controller(
+            state,
+            launch_id,
+            result.clone().map(|_| LaunchStatus::Completed),
+            ic_cdk::api::time(),
+        )
+    });
+
+    match (&result, record) {
+        (Ok(_), Some(record)) => {
+            if let Err(err) = settle_completed_launch(&record).await {
+                ic_cdk::println!("Failed to settle launch {}: {}", launch_id, err);
+            }
+        }
+        (Err(_), Some(record))
+            if record.failed_attempts.unwrap_or(0) >= crate::utils::MAX_LAUNCH_ATTEMPTS =>
+        {
+            if let Err(err) = roll_back_launch(record).await {
+                ic_cdk::println!("Failed to roll back launch {}: {}", launch_id, err);
+            }
+        }
+        _ => {}
+    }
+
+    result
+}
+
+// forwards the launch fee to the platform and the genesis contributions to the agent,
+// only what is still escrowed is sent so it is safe to run again
+async fn settle_completed_launch(record: &LaunchRecord) -> Result<(), String> {
+    let payment = settle_launch_payment(record.launch_id, true).await;
+    let contributions =
+        settle_genesis_contributions(record.launch_id, record.agent_canister_id).await;
+
+    payment.and(contributions)
+}
+
+// every step is skipped when an earlier run already completed it
+async fn run_launch_steps(launch_id: u64) -> Result<String, String> {
+    let mut record = with_state(|state| state.launch_records.get(&launch_id))
+        .ok_or(String::from(crate::utils::LAUNCH_NOT_FOUND))?;
+    let agent_detail = record.agent_input.clone();
+
+    if !with_state(|state| state.user_profile.contains_key(&record.owner)) {
+        return Err(String::from(crate::utils::USER_DOES_NOT_EXIST));
+    }
+
+    if record.last_completed_step < LaunchStep::ImageUploaded {
+        let image_id = upload_agent_image(&agent_detail, record.owner).await?;
+        record = checkpoint(launch_id, LaunchStep::ImageUploaded, |record| {
+            record.image_id = Some(image_id)
+        })?;
+    }
+
+    if record.last_completed_step < LaunchStep::AgentCanisterCreated {
+        let agent_canister_id = create_empty_canister()
+            .await
+            .map_err(|err| format!("{} {}", crate::utils::CREATE_AGENT_CANISTER_FAIL, err))?;
+        record = checkpoint(launch_id, LaunchStep::AgentCanisterCreated, |record| {
+            record.agent_canister_id = Some(agent_canister_id)
+        })?;
+    }
+    let agent_canister_id = record
+        .agent_canister_id
+        .ok_or(String::from(crate::utils::CREATE_AGENT_CANISTER_FAIL))?;
+
+    if record.last_completed_step < LaunchStep::AgentCyclesDeposited {
+        deposit_cycles(agent_canister_id, crate::utils::AGENT_CANISTER_CYCLES).await?;
+        record = checkpoint(launch_id, LaunchStep::AgentCyclesDeposited, |_| {})?;
+    }
+
+    if record.last_completed_step < LaunchStep::AgentCodeInstalled {
+        let image_id = record.image_id.clone().unwrap_or_default();
+        let wasm_version = install_agent_code(agent_canister_id, &agent_detail, image_id).await?;
+        record = checkpoint(launch_id, LaunchStep::AgentCodeInstalled, |record| {
+            record.agent_wasm_version = Some(wasm_version)
+        })?;
+    }
+
+    ic_cdk::println!("Agent canister created with id: {}", agent_canister_id.to_string());
+
+    if record.last_completed_step < LaunchStep::LedgerCanisterCreated {
+        let ledger_canister_id = create_empty_canister()
+            .await
+            .map_err(|err| format!("{} {}", crate::utils::CREATE_LEDGER_FAILURE, err))?;
+        record = checkpoint(launch_id, LaunchStep::LedgerCanisterCreated, |record| {
+            record.ledger_canister_id = Some(ledger_canister_id)
+        })?;
+    }
+    let ledger_canister_id = record
+        .ledger_canister_id
+        .ok_or(String::from(crate::utils::CREATE_LEDGER_FAILURE))?;
+
+    if record.last_completed_step < LaunchStep::LedgerCyclesDeposited {
+        deposit_cycles(ledger_canister_id, crate::utils::LEDGER_CANISTER_CYCLES).await?;
+        record = checkpoint(launch_id, LaunchStep::LedgerCyclesDeposited, |_| {})?;
+    }
+
+    if record.last_completed_step < LaunchStep::LedgerCodeInstalled {
+        let genesis_allocation = agent_detail
+            .token_config
+            .as_ref()
+            .map(|config| config.genesis_allocation)
+            .unwrap_or_default();
+        let shares = with_state(|state| genesis_shares(state, launch_id, genesis_allocation));
+
+        install_agent_ledger(
+            ledger_canister_id,
+            &agent_detail,
+            record.owner,
+            agent_canister_id,
+            shares,
+        )
+        .await?;
+        record = checkpoint(launch_id, LaunchStep::LedgerCodeInstalled, |_| {})?;
+    }
+
+    ic_cdk::println!("Ledger canister created with id: {}", ledger_canister_id.to_string());
+
+    if record.last_completed_step < LaunchStep::LedgerLinked {
+        call_inter_canister::<Principal, ()>(
+            "add_ledger_canister_id",
+            ledger_canister_id,
+            agent_canister_id,
+        )
+        .await
+        .map_err(|err| format!("{}{}", crate::utils::INTER_CANISTER_FAILED, err))?;
+        record = checkpoint(launch_id, LaunchStep::LedgerLinked, |_| {})?;
+    }
+
+    if record.last_completed_step < LaunchStep::Registered {
+        register_agent(&record, agent_canister_id, ledger_canister_id);
+        checkpoint(launch_id, LaunchStep::Registered, |_| {})?;
+    }
+
+    ic_cdk::println!(
+        "Agent created, canister id: {} ledger id: {}",
+        agent_canister_id.to_string(),
+        ledger_canister_id.to_string()
+    );
+
+    Ok(format!(
+        "Agent created, canister id: {} ledger id: {}",
+        agent_canister_id.to_string(),
+        ledger_canister_id.to_string()
+    ))
+}
+
+// lists the agent on the platform and on the profile of its creator
+fn register_agent(record: &LaunchRecord, agent_canister_id: Principal, ledger_canister_id: Principal) {
+    let agent_detail = record.agent_input.clone();
+
+    let agent_details = AgentDetails {
+        agent_canister_id,
+        agent_name: agent_detail.agent_name,
+        image_title: agent_detail.image_title,
+        agent_description : agent_detail.agent_description,
+        agent_associated_ledger : ledger_canister_id,
+        agent_category : agent_detail.agent_category,
+        agent_type : agent_detail.agent_type,
+        agent_overview : agent_detail.agent_overview,
+        members : agent_detail.members,
+        token_symbol : agent_detail.token_symbol,
+        token_supply : agent_detail.token_supply,
+        image_id : record.image_id.clone().unwrap_or(agent_detail.image_id),
+        agent_website : agent_detail.agent_website,
+        agent_twitter : agent_detail.agent_twitter,
+        agent_discord : agent_detail.agent_discord,
+        agent_telegram : agent_detail.agent_telegram,
+        token_name : agent_detail.token_name,
+        agent_lunch_time : agent_detail.agent_lunch_time,
+        agent_wasm_version : record.agent_wasm_version,
+    };
+
+    with_state(|state| {
+        state.agent_details.insert(agent_canister_id, agent_details);
+
+        if let Some(mut profile) = state.user_profile.get(&record.owner) {
+            let agents = profile.user_created_agents.get_or_insert_with(Vec::new);
+            if !agents.contains(&agent_canister_id) {
+                agents.push(agent_canister_id);
+            }
+            state.user_profile.insert(record.owner, profile);
+        }
+    });
+}
+
+async fn delete_orphaned_canister(canister_id: Principal) -> Result<(), String> {
+    // a canister has to be stopped before it can be deleted
+    stop_canister_by_id(CanisterIdRecord { canister_id })
+        .await
+        .map_err(|(_, err)| format!("Failed to stop canister {}: {}", canister_id, err))?;
+    delete_canister_by_id(CanisterIdRecord { canister_id })
+        .await
+        .map_err(|(_, err)| format!("Failed to delete canister {}: {}", canister_id, err))
+}
+
+// deletes the canisters a failed launch created and refunds its launch fee
+async fn rollback_launch_steps(record: LaunchRecord) -> Result<(), String> {
+    let launch_id = record.launch_id;
+
+    if let Some(ledger_canister_id) = record.ledger_canister_id {
+        delete_orphaned_canister(ledger_canister_id).await?;
+        checkpoint(launch_id, LaunchStep::AgentCodeInstalled, |record| {
+            record.ledger_canister_id = None
+        })?;
+    }
+
+    if let Some(agent_canister_id) = record.agent_canister_id {
+        delete_orphaned_canister(agent_canister_id).await?;
+        checkpoint(launch_id, LaunchStep::ImageUploaded, |record| {
+            record.agent_canister_id = None
+        })?;
+    }
+
+    if let Some(image_id) = record.image_id.as_ref().and_then(|id| id.parse::<u32>().ok()) {
+        // the image is only an asset, leaving it behind does not block the rollback
+        let asset_canister_id = with_state(|state| state.canister_data.get(&0))
+            .map(|data| data.ic_asset_canister);
+        if let Some(asset_canister_id) = asset_canister_id {
+            let result: Result<(Result<(), String>,), _> = ic_cdk::call(
+                asset_canister_id,
+                "delete_file",
+                (image_id, None::<serde_bytes::ByteBuf>),
+            )
+            .await;
+            if let Err((_, err)) = result {
+                ic_cdk::println!("Failed to delete launch image {}: {}", image_id, err);
+            }
+        }
+    }
+
+    settle_launch_payment(launch_id, false).await?;
+    settle_genesis_contributions(launch_id, None).await
+}
+
+// retries a failed launch from the last step it completed
+#[update(guard = prevent_anonymous)]
+async fn retry_launch(launch_id: u64) -> Result<String, String> {
+    let caller = ic_cdk::api::caller();
+
+    with_state(|state| {
+        resume_launch_controller(
+            state,
+            launch_id,
+            caller,
+            ic_cdk::api::is_controller(&caller),
+            ic_cdk::api::time(),
+        )
+    })?;
+
+    run_launch(launch_id).await
+}
+
+// settles a completed launch whose fee or genesis contributions could not be sent
+#[update(guard = prevent_anonymous)]
+async fn retry_launch_settlement(launch_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::api::caller();
+
+    let record = with_state(|state| {
+        completed_launch_of(
+            state,
+            launch_id,
+            caller,
+            ic_cdk::api::is_controller(&caller),
+            ic_cdk::api::time(),
+        )
+    })?;
+
+    settle_completed_launch(&record).await
+}
+
+#[update(guard = prevent_anonymous)]
+async fn rollback_launch(launch_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::api::caller();
+
+    let record = with_state(|state| {
+        resume_launch_controller(
+            state,
+            launch_id,
+            caller,
+            ic_cdk::api::is_controller(&caller),
+            ic_cdk::api::time(),
+        )
+    })?;
+
+    roll_back_launch(record).await
+}
+
+// a rollback that fails halfway leaves the launch Failed so it can be rolled back again
+async fn roll_back_launch(record: LaunchRecord) -> Result<(), String> {
+    let launch_id = record.launch_id;
+    let result = rollback_launch_steps(record).await;
+
+    with_state(|state| {
+        finish_launch_controller(
+            state,
+            launch_id,
+            result.clone().map(|_| LaunchStatus::RolledBack),
+            ic_cdk::api::time(),
+        )
+    });
+
+    result
+}
+
+#[query(guard = prevent_anonymous)]
+fn get_my_launches() -> Vec<LaunchRecord> {
+    let caller = ic_cdk::api::caller();
+
+    with_state(|state| {
+        state
+            .launch_records
+            .iter()
+            .map(|(_, record)| record)
+            .filter(|record| record.owner == caller)
+            .collect()
+    })
+}
+
+// launches that failed or never finished, for admins to retry or roll back
+#[query(guard = is_controller)]
+fn list_stuck_launches() -> Vec<LaunchRecord> {
+    with_state(|state| stuck_launches(state))
+}
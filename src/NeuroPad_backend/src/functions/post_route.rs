@@ -1,9 +1,19 @@
-use crate::{with_state, AgentDetails, AgentInput};
+use crate::routes::{
+    cancel_launch_controller, claim_launch_payment, finish_escrow, pending_launches_of,
+    start_launch_controller, validate_token_config,
+};
+use crate::{
+    with_state, AgentDetails, AgentInput, AgentMint, LaunchPayment, LaunchPaymentStatus, MintRequest,
+    PendingLaunch,
+};
 use candid::{Nat, Principal};
 use ic_cdk::update;
 use ic_cdk_timers::set_timer;
 use icrc_ledger_types::{
-    icrc1::{account::Account, transfer::BlockIndex},
+    icrc1::{
+        account::Account,
+        transfer::{BlockIndex, Memo, TransferArg, TransferError},
+    },
     icrc2::transfer_from::{TransferFromArgs, TransferFromError},
 };
 use std::time::Duration;
@@ -11,7 +21,8 @@ use std::time::Duration;
 use crate::guards::*;
 use ic_cdk::query;
 
-use super::create_agent;
+use super::genesis_route::settle_genesis_contributions;
+use super::run_launch;
 
 #[query(guard = prevent_anonymous)]
 fn get_all_agent() -> Vec<AgentDetails> {
@@ -24,13 +35,10 @@ fn get_all_agent() -> Vec<AgentDetails> {
     return agents;
 }
 
-async fn transfer(tokens: Nat, user_principal: Principal) -> Result<BlockIndex, String> {
+// pulls the launch fee from the user into the backend, where it stays until the launch settles
+pub(super) async fn transfer(tokens: Nat, user_principal: Principal) -> Result<BlockIndex, String> {
     let canister_meta_data = with_state(|state| state.canister_data.get(&0));
 
-    let payment_recipient = match canister_meta_data {
-        Some(val) => val.paymeny_recipient,
-        None => return Err(String::from(crate::utils::CANISTER_DATA_NOT_FOUND)),
-    };
     let neuropad_ledger_id = match canister_meta_data {
         Some(val) => val.neuropad_ledger_id,
         None => return Err(String::from(crate::utils::CANISTER_DATA_NOT_FOUND)),
@@ -39,7 +47,7 @@ async fn transfer(tokens: Nat, user_principal: Principal) -> Result<BlockIndex,
     let transfer_args = TransferFromArgs {
         amount: tokens,
         to: Account {
-            owner: payment_recipient,
+            owner: ic_cdk::api::id(),
             subaccount: None,
         },
         fee: None,
@@ -63,26 +71,327 @@ async fn transfer(tokens: Nat, user_principal: Principal) -> Result<BlockIndex,
     .map_err(|e| format!("ledger transfer error {:?}", e))
 }
 
-#[update]
+async fn neuropad_ledger_fee(neuropad_ledger_id: Principal) -> Result<Nat, String> {
+    ic_cdk::call::<(), (Nat,)>(neuropad_ledger_id, "icrc1_fee", ())
+        .await
+        .map(|res| res.0)
+        .map_err(|e| format!("failed to call ledger: {:?}", e))
+}
+
+// pays out tokens the backend holds in escrow for a launch, the ledger fee of the
+// transfer is taken from `amount`; the inner error means nothing was sent, the outer one
+// is a failed call the transfer may have gone through with. With a fixed `created_at_time`
+// a transfer sent again is deduplicated by the ledger and resolves to the first block
+pub(super) async fn send_escrowed_tokens(
+    neuropad_ledger_id: Principal,
+    to: Principal,
+    amount: Nat,
+    launch_id: u64,
+    created_at_time: Option<u64>,
+) -> Result<Result<Option<BlockIndex>, String>, String> {
+    let ledger_fee = match neuropad_ledger_fee(neuropad_ledger_id).await {
+        Ok(ledger_fee) => ledger_fee,
+        Err(err) => return Ok(Err(err)),
+    };
+
+    if amount <= ledger_fee {
+        // nothing left to move once the ledger fee is paid
+        return Ok(Ok(None));
+    }
+
+    let transfer_args = TransferArg {
+        from_subaccount: None,
+        to: Account {
+            owner: to,
+            subaccount: None,
+        },
+        fee: None,
+        created_at_time,
+        memo: Some(Memo::from(launch_id.to_be_bytes().to_vec())),
+        amount: amount - ledger_fee,
+    };
+
+    let result = ic_cdk::call::<(TransferArg,), (Result<BlockIndex, TransferError>,)>(
+        neuropad_ledger_id,
+        "icrc1_transfer",
+        (transfer_args,),
+    )
+    .await
+    .map_err(|e| format!("failed to call ledger: {:?}", e))?
+    .0;
+
+    match result {
+        Ok(block_index) => Ok(Ok(Some(block_index))),
+        Err(TransferError::Duplicate { duplicate_of }) => Ok(Ok(Some(duplicate_of))),
+        // past the deduplication window an earlier attempt can no longer be told apart
+        Err(TransferError::TooOld) => Err(String::from("ledger transfer error TooOld")),
+        Err(e) => Ok(Err(format!("ledger transfer error {:?}", e))),
+    }
+}
+
+// forwards an escrowed launch fee to the payment recipient once the agent is live,
+// or gives it back to the payer when the launch failed
+pub async fn settle_launch_payment(launch_id: u64, launched: bool) -> Result<(), String> {
+    let canister_meta_data = with_state(|state| state.canister_data.get(&0))
+        .ok_or(String::from(crate::utils::CANISTER_DATA_NOT_FOUND))?;
+
+    // claimed before the transfer so a concurrent settlement does not send it again
+    let claim = with_state(|state| claim_launch_payment(state, launch_id, ic_cdk::api::time()));
+    let Some((payment, created_at_time)) = claim else {
+        return Ok(());
+    };
+
+    let (to, settled_status) = if launched {
+        (canister_meta_data.paymeny_recipient, LaunchPaymentStatus::Forwarded)
+    } else {
+        (payment.payer, LaunchPaymentStatus::Refunded)
+    };
+
+    let result = send_escrowed_tokens(
+        canister_meta_data.neuropad_ledger_id,
+        to,
+        payment.amount,
+        launch_id,
+        Some(created_at_time),
+    )
+    .await;
+
+    with_state(|state| {
+        if let Some(mut payment) = state.launch_payments.get(&launch_id) {
+            finish_escrow(
+                &mut payment.payment_status,
+                &mut payment.settlement_created_at,
+                &mut payment.settled_block_index,
+                &result,
+                settled_status,
+            );
+            state.launch_payments.insert(launch_id, payment);
+        }
+    });
+
+    result?.map(|_| ())
+}
+
+#[update(guard = prevent_anonymous)]
 async fn make_payment_and_create_agent(agent_details: AgentInput) -> Result<String, String> {
-    let agent_clone = agent_details.clone();
     let principal_id = ic_cdk::api::caller();
     let now = ic_cdk::api::time();
-    let trigger_at = agent_details.agent_lunch_time;
-    let delay = trigger_at.saturating_sub(now);
 
-    set_timer(Duration::from_nanos(delay), move || {
-        let agent_clone = agent_clone.clone();
-        ic_cdk::spawn(async move {
-            let result = create_agent(agent_clone, principal_id).await;
-            match result {
-                Ok(success_msg) => ic_cdk::println!("Agent created: {}", success_msg),
-                Err(err_msg) => ic_cdk::println!("Failed to create agent: {}", err_msg),
-            }
+    validate_token_config(&agent_details)?;
+
+    let launch_fee = with_state(|state| state.canister_data.get(&0))
+        .map(|data| data.launch_fee.unwrap_or(0))
+        .ok_or(String::from(crate::utils::CANISTER_DATA_NOT_FOUND))?;
+
+    let launch_id = with_state(|state| state.next_launch_id());
+
+    if launch_fee > 0 {
+        let payment_block_index = transfer(Nat::from(launch_fee), principal_id)
+            .await
+            .map_err(|err| format!("{}{}", crate::utils::LAUNCH_PAYMENT_FAILED, err))?;
+
+        with_state(|state| {
+            state.launch_payments.insert(
+                launch_id,
+                LaunchPayment {
+                    launch_id,
+                    payer: principal_id,
+                    amount: Nat::from(launch_fee),
+                    payment_block_index,
+                    payment_status: LaunchPaymentStatus::Escrowed,
+                    settled_block_index: None,
+                    paid_at: now,
+                    settlement_created_at: None,
+                },
+            )
         });
+    }
+
+    let scheduled_at = agent_details.agent_lunch_time;
+
+    with_state(|state| {
+        state.pending_launches.insert(
+            launch_id,
+            PendingLaunch {
+                launch_id,
+                owner: principal_id,
+                agent_input: agent_details,
+                scheduled_at,
+                created_at: now,
+            },
+        )
     });
 
-    Ok("Timer set to create agent later ✅".to_string())
+    schedule_launch(launch_id, scheduled_at);
+
+    Ok(format!("Agent launch {} scheduled ✅", launch_id))
+}
+
+// `scheduled_at` is an absolute time in nanoseconds
+fn schedule_launch(launch_id: u64, scheduled_at: u64) {
+    let delay = scheduled_at.saturating_sub(ic_cdk::api::time());
+
+    set_timer(Duration::from_nanos(delay), move || {
+        ic_cdk::spawn(run_pending_launch(launch_id));
+    });
+}
+
+async fn run_pending_launch(launch_id: u64) {
+    // a cancelled launch is no longer in the queue
+    let Some(launch) = with_state(|state| state.pending_launches.remove(&launch_id)) else {
+        return;
+    };
+
+    with_state(|state| start_launch_controller(state, launch, ic_cdk::api::time()));
+
+    match run_launch(launch_id).await {
+        Ok(success_msg) => ic_cdk::println!("Agent created: {}", success_msg),
+        Err(err_msg) => ic_cdk::println!("Failed to create agent: {}", err_msg),
+    }
+}
+
+// timers are lost on upgrade, so every queued launch is scheduled again
+pub fn rearm_pending_launches() {
+    let launches: Vec<(u64, u64)> = with_state(|state| {
+        state
+            .pending_launches
+            .iter()
+            .map(|(launch_id, launch)| (launch_id, launch.scheduled_at))
+            .collect()
+    });
+
+    for (launch_id, scheduled_at) in launches {
+        schedule_launch(launch_id, scheduled_at);
+    }
+}
+
+#[query(guard = prevent_anonymous)]
+fn get_my_pending_launches() -> Vec<PendingLaunch> {
+    with_state(|state| pending_launches_of(state, ic_cdk::api::caller()))
+}
+
+// cancels a scheduled launch and refunds its launch fee and genesis contributions
+#[update(guard = prevent_anonymous)]
+async fn cancel_launch(launch_id: u64) -> Result<(), String> {
+    with_state(|state| {
+        cancel_launch_controller(state, launch_id, ic_cdk::api::caller(), ic_cdk::api::time())
+    })?;
+
+    settle_launch_payment(launch_id, false).await?;
+    settle_genesis_contributions(launch_id, None).await
+}
+
+#[query(guard = prevent_anonymous)]
+fn get_my_launch_payments() -> Vec<LaunchPayment> {
+    let caller = ic_cdk::api::caller();
+
+    with_state(|state| {
+        state
+            .launch_payments
+            .iter()
+            .map(|(_, payment)| payment)
+            .filter(|payment| payment.payer == caller)
+            .collect()
+    })
+}
+
+#[update(guard = is_controller)]
+fn set_launch_fee(launch_fee: u64) -> Result<(), String> {
+    with_state(|state| match state.canister_data.get(&0) {
+        Some(mut data) => {
+            data.launch_fee = Some(launch_fee);
+            state.canister_data.insert(0, data);
+            Ok(())
+        }
+        None => Err(String::from(crate::utils::CANISTER_DATA_NOT_FOUND)),
+    })
+}
+
+// called by an agent canister after its member groups changed through a proposal
+#[update(guard = prevent_anonymous)]
+fn sync_agent_members(members: Vec<Principal>) -> Result<(), String> {
+    let agent_canister_id = ic_cdk::api::caller();
+
+    with_state(|state| match state.agent_details.get(&agent_canister_id) {
+        Some(mut agent) => {
+            agent.members = members;
+            state.agent_details.insert(agent_canister_id, agent);
+            Ok(())
+        }
+        None => Err(String::from(crate::utils::AGENT_NOT_FOUND)),
+    })
+}
+
+// the backend is the minting account of every agent ledger, so an agent canister asks it
+// to mint into the agent treasury once a mint proposal is accepted
+#[update(guard = prevent_anonymous)]
+async fn mint_agent_tokens(args: MintRequest) -> Result<Nat, String> {
+    let agent_canister_id = ic_cdk::api::caller();
+
+    let agent_ledger_id = with_state(|state| state.agent_details.get(&agent_canister_id))
+        .map(|agent| agent.agent_associated_ledger)
+        .ok_or(String::from(crate::utils::AGENT_NOT_FOUND))?;
+
+    if args.amount == Nat::from(0u64) {
+        return Err(String::from("Mint amount must be greater than zero !"));
+    }
+
+    let mint_key = format!("{}:{}", agent_canister_id, args.proposal_id);
+    if let Some(mint) = with_state(|state| state.agent_mints.get(&mint_key)) {
+        // the agent retried a mint that already went through
+        return Ok(mint.block_index);
+    }
+
+    let transfer_args = TransferArg {
+        from_subaccount: None,
+        to: Account {
+            owner: agent_canister_id,
+            subaccount: None,
+        },
+        fee: None,
+        created_at_time: args.created_at_time,
+        // the proposal id ties the mint block to the proposal that approved it
+        memo: Some(Memo::from(args.proposal_id.clone().into_bytes())),
+        amount: args.amount.clone(),
+    };
+
+    let result = ic_cdk::call::<(TransferArg,), (Result<BlockIndex, TransferError>,)>(
+        agent_ledger_id,
+        "icrc1_transfer",
+        (transfer_args,),
+    )
+    .await
+    .map_err(|e| format!("failed to call ledger: {:?}", e))?
+    .0;
+
+    let block_index = match result {
+        Ok(block_index) => block_index,
+        Err(TransferError::Duplicate { duplicate_of }) => duplicate_of,
+        Err(e) => return Err(format!("ledger transfer error {:?}", e)),
+    };
+
+    with_state(|state| {
+        state.agent_mints.insert(
+            mint_key,
+            AgentMint {
+                agent_canister_id,
+                proposal_id: args.proposal_id.clone(),
+                amount: args.amount.clone(),
+                block_index: block_index.clone(),
+                minted_at: ic_cdk::api::time(),
+            },
+        )
+    });
+
+    ic_cdk::println!(
+        "Minted {} tokens for agent {} (proposal {}) at block {}",
+        args.amount,
+        agent_canister_id,
+        args.proposal_id,
+        block_index
+    );
+
+    Ok(block_index)
 }
 
 #[query(guard = prevent_anonymous)]
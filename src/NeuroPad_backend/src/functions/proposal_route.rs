@@ -0,0 +1,43 @@
+use crate::guards::*;
+use crate::routes::{add_proposal_controller, is_open_proposal, list_proposals_controller};
+use crate::{with_state, Pagination, ProposalValueStore};
+use candid::Principal;
+use ic_cdk::{query, update};
+
+// called by agent canisters whenever one of their proposals is created or changes state
+#[update(guard = prevent_anonymous)]
+fn sync_agent_proposal(mut args: ProposalValueStore) -> Result<String, String> {
+    // an agent can only report its own proposals
+    args.associated_agent_canister_id = ic_cdk::api::caller();
+
+    with_state(|state| add_proposal_controller(state, args))
+}
+
+#[query(guard = prevent_anonymous)]
+fn list_proposals_by_agent(agent_canister_id: Principal, page: Pagination) -> Vec<ProposalValueStore> {
+    with_state(|state| {
+        list_proposals_controller(state, page.start, page.end, |proposal| {
+            proposal.associated_agent_canister_id == agent_canister_id
+        })
+    })
+}
+
+#[query(guard = prevent_anonymous)]
+fn list_open_proposals(page: Pagination) -> Vec<ProposalValueStore> {
+    let now = ic_cdk::api::time();
+
+    with_state(|state| {
+        list_proposals_controller(state, page.start, page.end, |proposal| {
+            is_open_proposal(proposal, now)
+        })
+    })
+}
+
+#[query(guard = prevent_anonymous)]
+fn list_proposals_created_by(created_by: Principal, page: Pagination) -> Vec<ProposalValueStore> {
+    with_state(|state| {
+        list_proposals_controller(state, page.start, page.end, |proposal| {
+            proposal.created_by == created_by
+        })
+    })
+}
@@ -0,0 +1,114 @@
+use crate::guards::*;
+use crate::routes::{
+    has_queued_upgrades, queue_agent_upgrades, record_agent_upgrade, requeue_interrupted_upgrades,
+    take_upgrade_batch,
+};
+use crate::types::{CanisterIdRecord, CanisterInstallMode};
+use crate::{with_state, AgentUpgradeState, AgentUpgradeStatus, UpgradeAgentsArgs};
+use candid::Principal;
+use ic_cdk::{query, update};
+use ic_cdk_timers::set_timer;
+use std::time::Duration;
+
+use super::canister_factory::{install_wasm, start_canister_by_id, stop_canister_by_id};
+
+// queues the agents for an upgrade to `wasm_version`, the rollout runs in batches on timers
+#[update(guard = is_controller)]
+fn upgrade_agents(args: UpgradeAgentsArgs) -> Result<u32, String> {
+    let batch_size = args
+        .batch_size
+        .unwrap_or(crate::utils::DEFAULT_UPGRADE_BATCH_SIZE)
+        .max(1);
+
+    let queued = with_state(|state| {
+        queue_agent_upgrades(state, args.wasm_version, args.agent_ids, ic_cdk::api::time())
+    })?;
+
+    if with_state(|state| has_queued_upgrades(state, true)) {
+        schedule_upgrade_batch(batch_size);
+    }
+
+    Ok(queued)
+}
+
+#[query(guard = is_controller)]
+fn get_agent_upgrade_statuses() -> Vec<AgentUpgradeStatus> {
+    with_state(|state| state.agent_upgrades.iter().map(|(_, status)| status).collect())
+}
+
+fn schedule_upgrade_batch(batch_size: u32) {
+    set_timer(Duration::ZERO, move || {
+        ic_cdk::spawn(run_upgrade_batch(batch_size));
+    });
+}
+
+// upgrades one batch of pending agents and schedules the next one while agents are left
+async fn run_upgrade_batch(batch_size: u32) {
+    let batch = with_state(|state| take_upgrade_batch(state, batch_size, ic_cdk::api::time()));
+    if batch.is_empty() {
+        return;
+    }
+
+    for status in batch {
+        let result = match status.upgrade_state {
+            // the new code is already installed, the agent only has to be started again
+            AgentUpgradeState::Stopped => Ok(start_agent(status.agent_canister_id).await.err()),
+            _ => upgrade_agent_canister(&status).await,
+        };
+        match &result {
+            Err(err) => {
+                ic_cdk::println!("Failed to upgrade agent {}: {}", status.agent_canister_id, err)
+            }
+            Ok(Some(err)) => ic_cdk::println!(
+                "Failed to start agent {} after its upgrade: {}",
+                status.agent_canister_id,
+                err
+            ),
+            Ok(None) => {}
+        }
+
+        with_state(|state| record_agent_upgrade(state, status, result, ic_cdk::api::time()));
+    }
+
+    if with_state(|state| has_queued_upgrades(state, false)) {
+        schedule_upgrade_batch(batch_size);
+    }
+}
+
+// `Ok` once the new code is installed, with the error of starting the agent again if that failed
+async fn upgrade_agent_canister(status: &AgentUpgradeStatus) -> Result<Option<String>, String> {
+    let wasm_module = with_state(|state| state.wasm_module.get(&status.wasm_version))
+        .map(|val| val.wasm)
+        .ok_or(String::from(crate::utils::WASM_VERSION_NOT_FOUND))?;
+
+    // the agent post_upgrade takes no arguments
+    let arg = candid::encode_args(()).map_err(|err| err.to_string())?;
+
+    let canister_id = status.agent_canister_id;
+
+    // stopping lets in-flight calls of the agent finish before its code is replaced
+    let installed = match stop_canister_by_id(CanisterIdRecord { canister_id }).await {
+        Ok(()) => {
+            install_wasm(CanisterInstallMode::Upgrade(None), canister_id, wasm_module, arg).await
+        }
+        Err((_, err)) => Err(format!("Failed to stop canister {}: {}", canister_id, err)),
+    };
+
+    // the agent is started again whether or not the upgrade went through
+    let started = start_agent(canister_id).await;
+
+    installed.map(|()| started.err())
+}
+
+async fn start_agent(canister_id: Principal) -> Result<(), String> {
+    start_canister_by_id(CanisterIdRecord { canister_id })
+        .await
+        .map_err(|(_, err)| format!("Failed to start canister {}: {}", canister_id, err))
+}
+
+// timers are lost on upgrade, so a rollout that was still running continues afterwards
+pub fn rearm_agent_upgrades() {
+    if with_state(requeue_interrupted_upgrades) {
+        schedule_upgrade_batch(crate::utils::DEFAULT_UPGRADE_BATCH_SIZE);
+    }
+}
@@ -1,16 +1,13 @@
-use crate::routes::{create_agent_canister, create_new_ledger_canister};
-use crate::types::{AgentInput, Profileinput, UserProfile};
+use crate::types::{Profileinput, UserProfile};
 use crate::{
-    guards::*, Account, ArchiveOptions,
-    FeatureFlags, InitArgs, LedgerArg,
+    guards::*, Account, AgentInput, ArchiveOptions,
+    FeatureFlags, InitArgs, LedgerArg, Metadata, MetadataValue,
 };
-use crate::{routes, with_state, AgentDetails};
+use crate::{routes, with_state};
 use candid::{Nat, Principal};
 use ic_cdk::api;
 use ic_cdk::{query, update};
-
-use super::canister_functions::call_inter_canister;
-use super::ledger_functions::create_ledger_canister;
+use sha2::{Digest, Sha256};
 
 
 #[query(guard = prevent_anonymous)]
@@ -52,119 +49,6 @@ async fn create_user_profile(
     Ok(())
 }
 
-pub async fn create_agent(agent_detail: AgentInput, principal_id : Principal) -> Result<String, String> {
-    let user_profile_detail = with_state(|state| state.user_profile.get(&principal_id).clone());
-
-    let mut user_profile_detail = match user_profile_detail {
-        Some(data) => data,
-        None => return Err(String::from(crate::utils::USER_DOES_NOT_EXIST)),
-    };
-
-    ic_cdk::println!("Creating agent with details: {:?}", agent_detail);
-
-    let agent_canister_id = create_agent_canister(agent_detail.clone())
-        .await
-        .map_err(|err| format!("{} {}", crate::utils::CREATE_AGENT_CANISTER_FAIL, err))?;
-
-    ic_cdk::println!("Agent canister created with id: {}", agent_canister_id.to_string());
-
-    // to create ledger canister
-    let ledger_canister_id = create_new_ledger_canister(agent_detail.clone(), agent_canister_id).await;
-
-     user_profile_detail.user_created_agents = match user_profile_detail.user_created_agents {
-        Some(mut agents) => {
-            agents.push(agent_canister_id.clone());
-            Some(agents)
-        }
-        None => Some(vec![agent_canister_id.clone()]),
-    };
-
-    let res = match ledger_canister_id {
-        Ok(val) => Ok(val),
-
-        Err(err) => {
-            Err(format!("{} {}", crate::utils::CREATE_LEDGER_FAILURE, err))
-        }
-    }
-    .map_err(|err| format!("Error {}", err));
-
-    let ledger_canister_id = res.map_err(|err| format!("Error in ledger canister id: {}", err))?;
-
-    ic_cdk::println!("Ledger canister created with id: {}", ledger_canister_id.to_string());
-
-    let agent_details = AgentDetails {
-        agent_canister_id: agent_canister_id.clone(),
-        agent_name: agent_detail.agent_name,
-        image_title: agent_detail.image_title,
-        agent_description : agent_detail.agent_description,
-        agent_associated_ledger : ledger_canister_id.clone(),
-        agent_category : agent_detail.agent_category,
-        agent_type : agent_detail.agent_type,
-        agent_overview : agent_detail.agent_overview,
-        members : agent_detail.members,
-        token_symbol : agent_detail.token_symbol,
-        token_supply : agent_detail.token_supply,
-        image_id : agent_detail.image_id,
-        agent_website : agent_detail.agent_website,
-        agent_twitter : agent_detail.agent_twitter,
-        agent_discord : agent_detail.agent_discord,
-        agent_telegram : agent_detail.agent_telegram,
-        token_name : agent_detail.token_name,
-        agent_lunch_time : agent_detail.agent_lunch_time,
-    };
-
-    with_state(|state| {
-        state
-            .agent_details
-            .insert(agent_canister_id.clone(), agent_details)
-    });
-
-
-    // with_state(|state| {
-    //     state
-    //         .user_profile
-    //         .insert(ic_cdk::api::caller(), agent_details)
-    // });
-
-    // user_profile_detail.agent_ids.push(agent_canister_id.clone());
-
-    match call_inter_canister::<Principal, ()>(
-        "add_ledger_canister_id",
-        ledger_canister_id,
-        agent_canister_id,
-    )
-    .await
-    {
-        Ok(()) => { ic_cdk::println!("Ledger canister id added to agent canister successfully"); }
-        Err(err) => {
-            return Err(format!("{}{}", crate::utils::INTER_CANISTER_FAILED, err));
-        }
-    }
-
-    ic_cdk::println!("Agent created, canister id: {} ledger id: {}", agent_canister_id.to_string(), ledger_canister_id.to_string());
-    
-    with_state(|state| {
-        if let Some(profile) = state.user_profile.get(&principal_id) {
-            let updated_profile = profile.clone();
-            // updated_profile.join_agent.push(agent_canister_id.clone());
-            ic_cdk::println!("updated profile {:?}", updated_profile);
-            state.user_profile.insert(principal_id, updated_profile);
-        }
-    });
-
-    ic_cdk::println!(
-        "Agent created, canister id: {} ledger id: {}",
-        agent_canister_id.to_string().clone(),
-        ledger_canister_id.to_string().clone()
-    );
-
-    Ok(format!(
-        "Agent created, canister id: {} ledger id: {}",
-        agent_canister_id.to_string(),
-        ledger_canister_id.to_string()
-    ))
-}
-
 #[query(guard = prevent_anonymous)]
 fn check_user_existance() -> Result<String, String> {
     with_state(|state| {
@@ -177,61 +61,157 @@ fn check_user_existance() -> Result<String, String> {
     })
 }
 
-// #[update]
-pub async fn create_ledger(
-    total_tokens: Nat,
-    token_name: String,
-    token_symbol: String,
-    members: Vec<Principal>,
+// init args of an agent token ledger, the backend is the minting account.
+// expects a token config that already passed `validate_token_config`
+pub fn ledger_init_args(
+    agent_detail: &AgentInput,
+    creator: Principal,
     agent_canister_id: Principal,
-) -> Result<Principal, String> {
-    let tokens_per_user = total_tokens.clone() / members.len();
-
-    let mut accounts: Vec<(Account, Nat)> = vec![];
+    genesis_shares: Vec<(Principal, Nat)>,
+) -> LedgerArg {
+    let treasury = Account {
+        owner: agent_canister_id,
+        subaccount: None,
+    };
+    let total_tokens = Nat::from(agent_detail.token_supply);
+
+    let Some(config) = agent_detail.token_config.as_ref() else {
+        return LedgerArg::Init(InitArgs {
+            token_name: agent_detail.token_name.clone(),
+            token_symbol: agent_detail.token_symbol.clone(),
+            minting_account: minting_account(),
+            transfer_fee: Nat::from(0 as u32),
+            metadata: vec![],
+            initial_balances: vec![(treasury, total_tokens)],
+            archive_options: archive_options(),
+            feature_flags: Some(FeatureFlags { icrc2: true }),
+            fee_collector_account: None,
+            accounts_overflow_trim_quantity: None,
+            maximum_number_of_accounts: None,
+            decimals: None,
+            max_memo_length: None,
+        });
+    };
 
-    for acc in members.iter() {
-        let account = Account {
-            owner: acc.to_owned(),
-            subaccount: None,
-        };
+    let mut initial_balances: Vec<(Account, Nat)> = Vec::new();
+    let mut treasury_amount = Nat::from(config.treasury_allocation);
+
+    if config.members_allocation > 0 {
+        let members = &agent_detail.members;
+        let per_member = config.members_allocation / members.len() as u64;
+        let remainder = config.members_allocation - per_member * members.len() as u64;
+        treasury_amount = treasury_amount + Nat::from(remainder);
+
+        if per_member > 0 {
+            for member in members {
+                initial_balances.push((
+                    Account {
+                        owner: *member,
+                        subaccount: None,
+                    },
+                    Nat::from(per_member),
+                ));
+            }
+        }
+    }
 
-        accounts.push((account, tokens_per_user.clone()))
+    if config.creator_allocation > 0 {
+        initial_balances.push((
+            Account {
+                owner: creator,
+                subaccount: None,
+            },
+            Nat::from(config.creator_allocation),
+        ));
     }
 
-    let ledger_args = LedgerArg::Init(InitArgs {
-        token_name: token_name,
-        token_symbol: token_symbol,
-        minting_account: Account {
-            owner: ic_cdk::api::id(),
-            subaccount: None,
-        },
-        transfer_fee: Nat::from(0 as u32),
-        metadata: vec![],
-        initial_balances :  vec![(
+    // the agent canister releases these through its vesting schedules
+    for allocation in config.vesting.iter() {
+        initial_balances.push((
             Account {
                 owner: agent_canister_id,
+                subaccount: Some(vesting_subaccount(&allocation.beneficiary).to_vec()),
+            },
+            Nat::from(allocation.amount),
+        ));
+    }
+
+    // a genesis launch without contributors keeps its genesis allocation in the treasury
+    let mut genesis_left = Nat::from(config.genesis_allocation);
+    for (contributor, share) in genesis_shares {
+        genesis_left = genesis_left - share.clone();
+        initial_balances.push((
+            Account {
+                owner: contributor,
                 subaccount: None,
             },
-            total_tokens,
-        )],
-        archive_options: ArchiveOptions {
-            controller_id: api::caller(),
-            cycles_for_archive_creation: None,
-            max_message_size_bytes: None,
-            max_transactions_per_response: None,
-            node_max_memory_size_bytes: None,
-            num_blocks_to_archive: 100,
-            trigger_threshold: 100,
-        },
+            share,
+        ));
+    }
+    treasury_amount = treasury_amount + genesis_left;
+
+    if treasury_amount > Nat::from(0u64) {
+        initial_balances.push((treasury, treasury_amount));
+    }
+
+    let mut metadata = vec![];
+    if config.use_image_as_logo {
+        metadata.push(Metadata {
+            key: String::from("icrc1:logo"),
+            value: MetadataValue::Text(format!(
+                "data:{};base64,{}",
+                agent_detail.image_content_type,
+                base64::encode(&agent_detail.image_content)
+            )),
+        });
+    }
+
+    LedgerArg::Init(InitArgs {
+        token_name: agent_detail.token_name.clone(),
+        token_symbol: agent_detail.token_symbol.clone(),
+        minting_account: minting_account(),
+        transfer_fee: Nat::from(config.transfer_fee),
+        metadata,
+        initial_balances,
+        archive_options: archive_options(),
         feature_flags: Some(FeatureFlags { icrc2: true }),
-        fee_collector_account: None,
+        fee_collector_account: config.fee_collector.map(|owner| Account {
+            owner,
+            subaccount: None,
+        }),
         accounts_overflow_trim_quantity: None,
         maximum_number_of_accounts: None,
-        decimals: None,
-
+        decimals: Some(config.decimals),
         max_memo_length: None,
-    });
+    })
+}
+
+// has to match the vesting subaccounts the agent canister derives
+pub fn vesting_subaccount(beneficiary: &Principal) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"vesting:");
+    hasher.update(beneficiary.as_slice());
 
-    create_ledger_canister(ledger_args).await
+    let mut subaccount = [0u8; 32];
+    subaccount.copy_from_slice(&hasher.finalize());
+    subaccount
+}
 
+fn minting_account() -> Account {
+    Account {
+        owner: ic_cdk::api::id(),
+        subaccount: None,
+    }
+}
+
+fn archive_options() -> ArchiveOptions {
+    ArchiveOptions {
+        controller_id: api::id(),
+        cycles_for_archive_creation: None,
+        max_message_size_bytes: None,
+        max_transactions_per_response: None,
+        node_max_memory_size_bytes: None,
+        num_blocks_to_archive: crate::utils::LEDGER_NUM_BLOCKS_TO_ARCHIVE,
+        trigger_threshold: crate::utils::LEDGER_ARCHIVE_TRIGGER_THRESHOLD,
+    }
 }
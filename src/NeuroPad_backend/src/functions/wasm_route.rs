@@ -0,0 +1,88 @@
+use crate::guards::*;
+use crate::types::ChunkHash;
+use crate::{with_state, WasmArgs, WasmKind, WasmVersion};
+use ic_cdk::{query, update};
+
+use super::canister_factory::wasm_module_hash;
+
+// appends a chunk to the upload in progress, the returned hash lets the uploader
+// check that the chunk arrived intact
+#[update(guard = is_controller)]
+fn upload_wasm_chunk(kind: WasmKind, chunk: serde_bytes::ByteBuf) -> Result<ChunkHash, String> {
+    if chunk.is_empty() {
+        return Err(String::from("Wasm chunk cannot be empty."));
+    }
+
+    let hash = wasm_module_hash(&chunk);
+
+    with_state(|state| {
+        let mut upload = state.wasm_uploads.get(&kind.upload_key()).unwrap_or(WasmArgs {
+            wasm: Vec::new(),
+            wasm_module_hash: None,
+        });
+        upload.wasm.extend_from_slice(&chunk);
+        state.wasm_uploads.insert(kind.upload_key(), upload);
+    });
+
+    Ok(ChunkHash { hash })
+}
+
+// stores the uploaded chunks as the next version once they hash to `module_hash`,
+// new installs always use the newest version
+#[update(guard = is_controller)]
+fn commit_wasm_upload(kind: WasmKind, module_hash: serde_bytes::ByteBuf) -> Result<u64, String> {
+    with_state(|state| {
+        let upload = state
+            .wasm_uploads
+            .get(&kind.upload_key())
+            .ok_or(String::from(crate::utils::WASM_NOT_UPLOADED))?;
+
+        let uploaded_hash = wasm_module_hash(&upload.wasm);
+        if uploaded_hash != module_hash.into_vec() {
+            return Err(String::from(
+                "Uploaded wasm does not match the given sha256 hash, upload it again.",
+            ));
+        }
+
+        let version = state
+            .wasm_versions(kind)
+            .last_key_value()
+            .map(|(version, _)| version + 1)
+            .unwrap_or(crate::utils::FIRST_WASM_VERSION);
+
+        let wasm = WasmArgs {
+            wasm: upload.wasm,
+            wasm_module_hash: Some(uploaded_hash),
+        };
+        match kind {
+            WasmKind::Agent => state.wasm_module.insert(version, wasm),
+            WasmKind::Ledger => state.ledger_wasm_module.insert(version, wasm),
+        };
+        state.wasm_uploads.remove(&kind.upload_key());
+
+        Ok(version)
+    })
+}
+
+// drops the chunks of an upload that went wrong
+#[update(guard = is_controller)]
+fn cancel_wasm_upload(kind: WasmKind) {
+    with_state(|state| state.wasm_uploads.remove(&kind.upload_key()));
+}
+
+#[query(guard = is_controller)]
+fn list_wasm_versions(kind: WasmKind) -> Vec<WasmVersion> {
+    with_state(|state| {
+        state
+            .wasm_versions(kind)
+            .iter()
+            .map(|(version, wasm)| WasmVersion {
+                version,
+                size: wasm.wasm.len() as u64,
+                wasm_module_hash: wasm
+                    .wasm_module_hash
+                    .unwrap_or_else(|| wasm_module_hash(&wasm.wasm)),
+            })
+            .collect()
+    })
+}
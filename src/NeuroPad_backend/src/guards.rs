@@ -11,6 +11,14 @@ pub fn prevent_anonymous() -> Result<(), String> {
     Ok(())
 }
 
+// only controllers of the backend canister can change platform settings
+pub fn is_controller() -> Result<(), String> {
+    if !api::is_controller(&api::caller()) {
+        return Err(String::from(crate::utils::NOT_A_CONTROLLER));
+    }
+    Ok(())
+}
+
 // check user existance
 pub fn check_for_user_guard(user: &Principal) -> Result<(), String> {
     prevent_anonymous()?;
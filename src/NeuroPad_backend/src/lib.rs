@@ -1,6 +1,7 @@
 mod types;
-use ic_cdk::{api, export_candid, init};
-use std::{borrow::BorrowMut, cell::RefCell};
+use candid::{Nat, Principal};
+use ic_cdk::{api, export_candid, init, post_upgrade};
+use std::cell::RefCell;
 pub mod functions;
 pub mod guards;
 pub mod routes;
@@ -10,6 +11,8 @@ mod memory;
 pub use functions::*;
 use memory::Memory;
 pub mod utils;
+#[cfg(test)]
+mod test_fixtures;
 
 use types::*;
 
@@ -35,6 +38,7 @@ async fn init(args: InitialArgs) {
                     agent_canister: args.agent_canister_id,
                     paymeny_recipient: args.payment_recipient,
                     neuropad_ledger_id: args.neuropad_ledger_id,
+                    launch_fee: Some(args.launch_fee),
                 },
             );
         }
@@ -42,19 +46,14 @@ async fn init(args: InitialArgs) {
         ()
     });
 
-    with_state(|state| {
-        let agent_wasm_module: Vec<u8> =
-            include_bytes!("../../../.dfx/local/canisters/agent_canister/agent_canister.wasm").to_vec();
-
-        state.borrow_mut().wasm_module.insert(
-            0,
-            WasmArgs {
-                wasm: agent_wasm_module,
-            },
-        );
-
-    })
+    schedule_cycles_check();
 }
 
+#[post_upgrade]
+fn post_upgrade() {
+    rearm_pending_launches();
+    rearm_agent_upgrades();
+    schedule_cycles_check();
+}
 
 export_candid!();
@@ -7,6 +7,17 @@ const AGENT_DATA: MemoryId = MemoryId::new(2);
 const WASM_DATA: MemoryId = MemoryId::new(4);
 const CANISTER_META_DATA: MemoryId = MemoryId::new(5);
 const TOKEN_STACK_STATE: MemoryId = MemoryId::new(7);
+const LAUNCH_PAYMENT_DATA: MemoryId = MemoryId::new(8);
+const PENDING_LAUNCH_DATA: MemoryId = MemoryId::new(9);
+const LAUNCH_COUNT: MemoryId = MemoryId::new(10);
+const LAUNCH_RECORD_DATA: MemoryId = MemoryId::new(11);
+const AGENT_UPGRADE_DATA: MemoryId = MemoryId::new(12);
+const WASM_UPLOAD_DATA: MemoryId = MemoryId::new(13);
+const LEDGER_WASM_DATA: MemoryId = MemoryId::new(14);
+const CYCLES_CONFIG: MemoryId = MemoryId::new(15);
+const CANISTER_CYCLES_DATA: MemoryId = MemoryId::new(16);
+const GENESIS_CONTRIBUTION_DATA: MemoryId = MemoryId::new(17);
+const AGENT_MINT_DATA: MemoryId = MemoryId::new(18);
 
 
 pub type Memory = VirtualMemory<DefaultMemoryImpl>;
@@ -34,3 +45,47 @@ pub fn get_canister_data_memory() -> Memory {
 pub fn get_token_stack_memory() -> Memory {
     MEMORY_MANAGER.with(|m| m.borrow().get(TOKEN_STACK_STATE))
 }
+
+pub fn get_launch_payment_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(LAUNCH_PAYMENT_DATA))
+}
+
+pub fn get_pending_launch_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(PENDING_LAUNCH_DATA))
+}
+
+pub fn get_launch_count_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(LAUNCH_COUNT))
+}
+
+pub fn get_launch_record_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(LAUNCH_RECORD_DATA))
+}
+
+pub fn get_agent_upgrade_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(AGENT_UPGRADE_DATA))
+}
+
+pub fn get_wasm_upload_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(WASM_UPLOAD_DATA))
+}
+
+pub fn get_ledger_wasm_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(LEDGER_WASM_DATA))
+}
+
+pub fn get_cycles_config_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(CYCLES_CONFIG))
+}
+
+pub fn get_canister_cycles_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(CANISTER_CYCLES_DATA))
+}
+
+pub fn get_genesis_contribution_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(GENESIS_CONTRIBUTION_DATA))
+}
+
+pub fn get_agent_mint_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(AGENT_MINT_DATA))
+}
@@ -9,3 +9,15 @@ pub use agent_controller::*;
 
 mod proposal_controllers;
 pub use proposal_controllers::*;
+
+mod launch_controller;
+pub use launch_controller::*;
+
+mod upgrade_controller;
+pub use upgrade_controller::*;
+
+mod cycles_controller;
+pub use cycles_controller::*;
+
+mod genesis_controller;
+pub use genesis_controller::*;
@@ -1,130 +1,303 @@
 use crate::functions::{
-    create_ledger, create_new_canister, deposit_cycles_in_canister, install_code_in_canister,
+    create_new_canister, deposit_cycles_in_canister, install_wasm, ledger_init_args,
 };
 use crate::CanisterSettings;
 use candid::encode_one;
-use crate::types::{CanisterInstallMode, CreateCanisterArgument, InstallCodeArgument};
-use crate::with_state;
+use crate::types::{CanisterIdRecord, CanisterInstallMode, CreateCanisterArgument};
+use crate::{with_state, AgentType};
 use candid::{Nat, Principal};
 
-pub async fn create_agent_canister(agent_detail: crate::AgentInput) -> Result<Principal, String> {
-    let updated_members = agent_detail.members.clone();
-
-    let image_id: Result<String, String> = super::upload_image(
+pub async fn upload_agent_image(
+    agent_detail: &crate::AgentInput,
+    owner: Principal,
+) -> Result<String, String> {
+    super::upload_file_for(
         crate::ImageData {
-            content: agent_detail.image_content,
+            content: agent_detail.image_content.clone(),
             name: agent_detail.image_title.clone(),
-            content_type: agent_detail.image_content_type,
+            content_type: agent_detail.image_content_type.clone(),
         },
+        owner,
     )
-    .await;
-
-    let mut id = String::new();
-    let image_create_res: bool = (match image_id {
-        Ok(value) => {
-            id = value;
-            Ok(())
-        }
-        Err(er) => {
-            ic_cdk::println!("error {}", er.to_string());
-            Err(())
-        }
+    .await
+    .map_err(|er| {
+        ic_cdk::println!("error {}", er.to_string());
+        String::from("Failed to upload image !.")
     })
-    .is_err();
-
-    if image_create_res {
-        return Err(String::from("Failed to upload image !."));
-    }
+}
 
-    let canister_id = with_state(|state| state.canister_data.get(&0));
+// empty canister controlled by the backend, code is installed by a later step
+pub async fn create_empty_canister() -> Result<Principal, String> {
+    let all_controllers = CanisterSettings {
+        controllers: Some(vec![ic_cdk::api::id()]),
+        ..Default::default()
+    };
 
-    let asset_canister_id = match canister_id {
-        Some(val) => val.ic_asset_canister,
-        None => return Err(String::from("Canister Meta data not found.")),
+    let arg = CreateCanisterArgument {
+        settings: Some(all_controllers),
     };
 
+    match create_new_canister(arg).await {
+        Ok((canister_id,)) => Ok(canister_id.canister_id),
+        Err((_, err_string)) => Err(format!("{} {}", crate::utils::CREATE_CANISTER_FAIL, err_string)),
+    }
+}
+
+pub async fn deposit_cycles(canister_id: Principal, cycles: u128) -> Result<(), String> {
+    deposit_cycles_in_canister(CanisterIdRecord { canister_id }, cycles)
+        .await
+        .map_err(|(_, err_string)| format!("Failed to deposit cycles: {}", err_string))
+}
+
+// installs the newest agent wasm, returns its version
+pub async fn install_agent_code(
+    canister_id: Principal,
+    agent_detail: &crate::AgentInput,
+    image_id: String,
+) -> Result<u64, String> {
+    let asset_canister_id = with_state(|state| state.canister_data.get(&0))
+        .map(|val| val.ic_asset_canister)
+        .ok_or(String::from("Canister Meta data not found."))?;
 
     let update_agent_detail = crate::AgentCanisterInput {
         agent_name: agent_detail.agent_name.clone(),
-        members: updated_members,
-        image_id: id.clone(),
+        members: agent_detail.members.clone(),
+        image_id,
         image_canister: asset_canister_id,
-        token_symbol: agent_detail.token_symbol,
+        token_symbol: agent_detail.token_symbol.clone(),
         token_supply: agent_detail.token_supply,
-        agent_description : agent_detail.agent_description,
-        agent_category : agent_detail.agent_category,
-        agent_type : agent_detail.agent_type,
-        agent_overview : agent_detail.agent_overview,
-        agent_website : agent_detail.agent_website,
-        agent_twitter : agent_detail.agent_twitter,
-        agent_discord : agent_detail.agent_discord,
-        agent_telegram : agent_detail.agent_telegram,
-        token_name : agent_detail.token_name,
+        agent_description : agent_detail.agent_description.clone(),
+        agent_category : agent_detail.agent_category.clone(),
+        agent_type : agent_detail.agent_type.clone(),
+        agent_overview : agent_detail.agent_overview.clone(),
+        agent_website : agent_detail.agent_website.clone(),
+        agent_twitter : agent_detail.agent_twitter.clone(),
+        agent_discord : agent_detail.agent_discord.clone(),
+        agent_telegram : agent_detail.agent_telegram.clone(),
+        token_name : agent_detail.token_name.clone(),
         agent_lunch_time : agent_detail.agent_lunch_time,
         members_count : agent_detail.members.len() as u32,
+        proposal_entry : agent_detail.proposal_entry.clone(),
+        vesting : agent_detail
+            .token_config
+            .as_ref()
+            .map(|config| config.vesting.clone()),
     };
 
     // encoding params that is to be passed to new canister
-    let agent_detail_bytes: Vec<u8> = match encode_one(&update_agent_detail) {
-        Ok(bytes) => bytes,
-        Err(e) => {
-            return Err(format!("Failed to serialize AgentInput: {}", e));
+    let agent_detail_bytes: Vec<u8> = encode_one(&update_agent_detail)
+        .map_err(|e| format!("Failed to serialize AgentInput: {}", e))?;
+
+    let (wasm_version, wasm_module) = with_state(|state| state.latest_agent_wasm())
+        .map(|(version, val)| (version, val.wasm))
+        .ok_or(String::from("Agent wasm module not found."))?;
+
+    install_wasm(CanisterInstallMode::Install, canister_id, wasm_module, agent_detail_bytes)
+        .await
+        .map_err(|err_string| format!("Failed to install agent code: {}", err_string))?;
+
+    Ok(wasm_version)
+}
+
+// checked before the launch fee is charged, so that a bad config never reaches the ledger
+pub fn validate_token_config(agent_detail: &crate::AgentInput) -> Result<(), String> {
+    let is_genesis = matches!(agent_detail.agent_type, AgentType::GenesisLaunch);
+
+    let Some(config) = agent_detail.token_config.as_ref() else {
+        if is_genesis {
+            return Err(String::from(
+                "Genesis launch needs a token config with a genesis allocation.",
+            ));
         }
+        return Ok(());
     };
 
-    // adding controllers of new canister
-    let all_controllers = CanisterSettings {
-        controllers: Some(vec![ic_cdk::api::caller(), ic_cdk::api::id()]),
-        ..Default::default()
-    };
+    if is_genesis && config.genesis_allocation == 0 {
+        return Err(String::from("Genesis launch needs a genesis allocation."));
+    }
+    if !is_genesis && config.genesis_allocation > 0 {
+        return Err(String::from("Only genesis launches can have a genesis allocation."));
+    }
 
-    let arg = CreateCanisterArgument {
-        settings: Some(all_controllers),
-    };
+    if config.decimals > crate::utils::MAX_TOKEN_DECIMALS {
+        return Err(format!(
+            "Token decimals cannot be more than {}.",
+            crate::utils::MAX_TOKEN_DECIMALS
+        ));
+    }
+
+    let allocated = [
+        config.treasury_allocation,
+        config.members_allocation,
+        config.creator_allocation,
+        config.vesting_reserved,
+        config.genesis_allocation,
+    ]
+    .iter()
+    .try_fold(0u64, |total, amount| total.checked_add(*amount))
+    .ok_or(String::from("Token allocations overflow."))?;
+
+    if allocated != agent_detail.token_supply as u64 {
+        return Err(format!(
+            "Token allocations add up to {} but the token supply is {}.",
+            allocated, agent_detail.token_supply
+        ));
+    }
 
-    let (canister_id,) = match create_new_canister(arg).await {
-        Ok(id) => id,
-        Err((_, err_string)) => {
-            return Err(err_string);
+    if config.members_allocation > 0 && agent_detail.members.is_empty() {
+        return Err(String::from("Members allocation needs at least one member."));
+    }
+    // the members allocation is split per listed member, so a member listed twice would get
+    // two shares
+    for (index, member) in agent_detail.members.iter().enumerate() {
+        if agent_detail.members[..index].contains(member) {
+            return Err(format!("Member {} is listed more than once.", member));
         }
-    };
+    }
 
-    let canister_id_principal = canister_id.canister_id;
+    if config.transfer_fee > agent_detail.token_supply as u64 {
+        return Err(String::from("Transfer fee cannot exceed the token supply."));
+    }
 
-    let _addcycles = deposit_cycles_in_canister(canister_id, 300_000_000_000)
-        .await
-        .unwrap();
+    validate_vesting(config)?;
 
-    let mut wasm_module: Vec<u8> = Vec::new();
+    if config.fee_collector == Some(Principal::anonymous()) {
+        return Err(String::from("Fee collector cannot be the anonymous principal."));
+    }
 
-    with_state(|state| match state.wasm_module.get(&0) {
-        Some(val) => {
-            wasm_module = val.wasm;
+    if config.use_image_as_logo {
+        if agent_detail.image_content.len() > crate::utils::MAX_LOGO_SIZE {
+            return Err(format!(
+                "Image is too large to be used as token logo, the limit is {} bytes.",
+                crate::utils::MAX_LOGO_SIZE
+            ));
         }
-        None => panic!("WASM error"),
-    });
-
-    let arg1 = InstallCodeArgument {
-        mode: CanisterInstallMode::Install,
-        canister_id: canister_id_principal,
-        wasm_module: wasm_module.clone(),
-        arg: agent_detail_bytes,
-    };
+        if !agent_detail.image_content_type.starts_with("image/") {
+            return Err(String::from("Token logo has to be an image."));
+        }
+    }
+
+    Ok(())
+}
 
-    let _installcode = install_code_in_canister(arg1, wasm_module).await.unwrap();
+fn validate_vesting(config: &crate::TokenConfig) -> Result<(), String> {
+    let mut beneficiaries: Vec<Principal> = Vec::new();
+    let mut vested_total: u64 = 0;
+
+    for allocation in config.vesting.iter() {
+        if allocation.beneficiary == Principal::anonymous() {
+            return Err(String::from("Vesting beneficiary cannot be the anonymous principal."));
+        }
+        if beneficiaries.contains(&allocation.beneficiary) {
+            return Err(format!(
+                "Beneficiary {} has more than one vesting allocation.",
+                allocation.beneficiary
+            ));
+        }
+        if allocation.amount == 0 {
+            return Err(String::from("Vesting allocation must be greater than zero."));
+        }
+        if allocation.vesting_seconds == 0 || allocation.cliff_seconds > allocation.vesting_seconds {
+            return Err(String::from(
+                "Vesting period must be set and cannot be shorter than the cliff.",
+            ));
+        }
 
-    Ok(canister_id_principal)
+        beneficiaries.push(allocation.beneficiary);
+        vested_total = vested_total
+            .checked_add(allocation.amount)
+            .ok_or(String::from("Vesting allocations overflow."))?;
+    }
+
+    if vested_total != config.vesting_reserved {
+        return Err(format!(
+            "Vesting allocations add up to {} but {} tokens are reserved for vesting.",
+            vested_total, config.vesting_reserved
+        ));
+    }
+
+    Ok(())
 }
 
-// create ledger canister
-pub async fn create_new_ledger_canister(agent_detail: crate::AgentInput, agent_canister_id : Principal) -> Result<Principal, String> {
-    create_ledger(
-        Nat::from(agent_detail.token_supply),
-        agent_detail.token_name,
-        agent_detail.token_symbol,
-        agent_detail.members,
-        agent_canister_id
-    )
-    .await
-    .map_err(|er| format!("Error while creating ledger canister {}", String::from(er)))
+// installs the ledger of the agent token with the allocations of its token config,
+// `genesis_shares` are the balances of the genesis contributors
+pub async fn install_agent_ledger(
+    ledger_canister_id: Principal,
+    agent_detail: &crate::AgentInput,
+    creator: Principal,
+    agent_canister_id: Principal,
+    genesis_shares: Vec<(Principal, Nat)>,
+) -> Result<(), String> {
+    let ledger_args = ledger_init_args(agent_detail, creator, agent_canister_id, genesis_shares);
+
+    crate::functions::install_ledger_code(ledger_canister_id, ledger_args)
+        .await
+        .map_err(|er| format!("{} {}", crate::utils::CREATE_LEDGER_FAILURE, er))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{agent_input, principal, token_config};
+
+    #[test]
+    fn accepts_a_balanced_config() {
+        assert!(validate_token_config(&agent_input(Some(token_config()))).is_ok());
+        assert!(validate_token_config(&agent_input(None)).is_ok());
+    }
+
+    #[test]
+    fn rejects_allocations_that_miss_the_supply() {
+        let mut config = token_config();
+        config.treasury_allocation -= 1;
+
+        assert!(validate_token_config(&agent_input(Some(config))).is_err());
+    }
+
+    #[test]
+    fn rejects_overflowing_allocations() {
+        let mut config = token_config();
+        config.treasury_allocation = u64::MAX;
+
+        assert!(validate_token_config(&agent_input(Some(config))).is_err());
+    }
+
+    #[test]
+    fn rejects_genesis_allocation_outside_genesis_launches() {
+        let mut config = token_config();
+        config.treasury_allocation -= 50;
+        config.genesis_allocation = 50;
+        assert!(validate_token_config(&agent_input(Some(config.clone()))).is_err());
+
+        let mut genesis = agent_input(Some(config));
+        genesis.agent_type = AgentType::GenesisLaunch;
+        assert!(validate_token_config(&genesis).is_ok());
+
+        genesis.token_config = None;
+        assert!(validate_token_config(&genesis).is_err());
+    }
+
+    #[test]
+    fn rejects_vesting_shorter_than_its_cliff() {
+        let mut config = token_config();
+        config.vesting[0].cliff_seconds = 200;
+
+        assert!(validate_token_config(&agent_input(Some(config))).is_err());
+    }
+
+    #[test]
+    fn rejects_members_allocation_without_members() {
+        let mut agent = agent_input(Some(token_config()));
+        agent.members.clear();
+
+        assert!(validate_token_config(&agent).is_err());
+    }
+
+    #[test]
+    fn rejects_duplicate_members() {
+        let mut agent = agent_input(Some(token_config()));
+        agent.members.push(principal(1));
+
+        assert!(validate_token_config(&agent).is_err());
+    }
 }
@@ -0,0 +1,83 @@
+use crate::{
+    state_handler::State, AgentCyclesReport, CanisterCycles, CanisterStatusResponse,
+    MonitoredCanister,
+};
+use candid::{Nat, Principal};
+
+// every agent and ledger canister the platform launched, with the agent they belong to
+pub fn monitored_canisters(state: &State) -> Vec<(Principal, Principal, MonitoredCanister)> {
+    state
+        .agent_details
+        .iter()
+        .flat_map(|(agent_canister_id, agent)| {
+            [
+                (agent_canister_id, agent_canister_id, MonitoredCanister::Agent),
+                (agent.agent_associated_ledger, agent_canister_id, MonitoredCanister::Ledger),
+            ]
+        })
+        .collect()
+}
+
+// whether the canister is below the top-up threshold
+pub fn needs_top_up(state: &State, status: &CanisterStatusResponse) -> bool {
+    status.cycles < Nat::from(state.cycles_config.get().min_cycles)
+}
+
+pub fn record_cycles_check(
+    state: &mut State,
+    (canister_id, agent_canister_id, canister_kind): (Principal, Principal, MonitoredCanister),
+    status: Result<CanisterStatusResponse, String>,
+    topped_up: Result<u64, String>,
+    now: u64,
+) {
+    let mut record = state.canister_cycles.get(&canister_id).unwrap_or(CanisterCycles {
+        canister_id,
+        agent_canister_id,
+        canister_kind,
+        cycles: Nat::from(0u64),
+        idle_cycles_burned_per_day: Nat::from(0u64),
+        topped_up_total: 0,
+        last_top_up_at: None,
+        last_error: None,
+        checked_at: now,
+    });
+
+    record.checked_at = now;
+    record.last_error = None;
+
+    match status {
+        Ok(status) => {
+            record.cycles = status.cycles;
+            record.idle_cycles_burned_per_day = status.idle_cycles_burned_per_day;
+        }
+        Err(err) => record.last_error = Some(err),
+    }
+
+    match topped_up {
+        Ok(0) => {}
+        Ok(amount) => {
+            record.cycles = record.cycles.clone() + Nat::from(amount);
+            record.topped_up_total = record.topped_up_total.saturating_add(amount);
+            record.last_top_up_at = Some(now);
+        }
+        Err(err) => record.last_error = Some(err),
+    }
+
+    state.canister_cycles.insert(canister_id, record);
+}
+
+pub fn agent_cycles_report(
+    state: &State,
+    agent_canister_id: Principal,
+) -> Result<AgentCyclesReport, String> {
+    let agent = state
+        .agent_details
+        .get(&agent_canister_id)
+        .ok_or(String::from(crate::utils::AGENT_NOT_FOUND))?;
+
+    Ok(AgentCyclesReport {
+        agent_canister_id,
+        agent: state.canister_cycles.get(&agent_canister_id),
+        ledger: state.canister_cycles.get(&agent.agent_associated_ledger),
+    })
+}
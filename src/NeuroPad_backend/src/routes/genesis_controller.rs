@@ -0,0 +1,233 @@
+use crate::{
+    routes::claim_escrow, state_handler::State, AgentInput, AgentType, GenesisContribution,
+    GenesisSummary, LaunchPaymentStatus,
+};
+use candid::{Nat, Principal};
+
+pub fn contribution_key(launch_id: u64, contributor: &Principal) -> String {
+    format!("{}:{}", launch_id, contributor)
+}
+
+fn genesis_allocation(agent_input: &AgentInput) -> u64 {
+    agent_input
+        .token_config
+        .as_ref()
+        .map(|config| config.genesis_allocation)
+        .unwrap_or_default()
+}
+
+// a genesis launch takes contributions while it is queued and its launch time is ahead
+pub fn check_genesis_window(state: &State, launch_id: u64, now: u64) -> Result<(), String> {
+    let launch = state
+        .pending_launches
+        .get(&launch_id)
+        .ok_or(String::from(crate::utils::GENESIS_WINDOW_CLOSED))?;
+
+    if !matches!(launch.agent_input.agent_type, AgentType::GenesisLaunch) {
+        return Err(String::from("Only genesis launches accept contributions."));
+    }
+    if now >= launch.scheduled_at {
+        return Err(String::from(crate::utils::GENESIS_WINDOW_CLOSED));
+    }
+
+    Ok(())
+}
+
+// adds to the escrowed contribution of the caller, returns its new total
+pub fn record_contribution(
+    state: &mut State,
+    launch_id: u64,
+    contributor: Principal,
+    amount: Nat,
+    now: u64,
+) -> Nat {
+    let key = contribution_key(launch_id, &contributor);
+
+    let mut contribution = state.genesis_contributions.get(&key).unwrap_or(GenesisContribution {
+        launch_id,
+        contributor,
+        amount: Nat::from(0u64),
+        contribution_status: LaunchPaymentStatus::Escrowed,
+        settled_block_index: None,
+        last_contributed_at: now,
+        settlement_created_at: None,
+    });
+    contribution.amount = contribution.amount.clone() + amount;
+    contribution.last_contributed_at = now;
+
+    let total = contribution.amount.clone();
+    state.genesis_contributions.insert(key, contribution);
+
+    total
+}
+
+pub fn escrowed_contributions(state: &State, launch_id: u64) -> Vec<GenesisContribution> {
+    state
+        .genesis_contributions
+        .iter()
+        .map(|(_, contribution)| contribution)
+        .filter(|contribution| {
+            contribution.launch_id == launch_id
+                && contribution.contribution_status == LaunchPaymentStatus::Escrowed
+        })
+        .collect()
+}
+
+// claims every contribution of a launch that is still to be settled, see `claim_escrow`
+pub fn claim_contributions(
+    state: &mut State,
+    launch_id: u64,
+    now: u64,
+) -> Vec<(GenesisContribution, u64)> {
+    let contributions: Vec<(String, GenesisContribution)> = state
+        .genesis_contributions
+        .iter()
+        .filter(|(_, contribution)| contribution.launch_id == launch_id)
+        .collect();
+
+    contributions
+        .into_iter()
+        .filter_map(|(key, mut contribution)| {
+            let created_at_time = claim_escrow(
+                &mut contribution.contribution_status,
+                &mut contribution.settlement_created_at,
+                now,
+            )?;
+            state.genesis_contributions.insert(key, contribution.clone());
+            Some((contribution, created_at_time))
+        })
+        .collect()
+}
+
+// pro-rata share of the genesis allocation for every contributor,
+// whatever is lost to rounding stays with the agent treasury
+pub fn genesis_shares(
+    state: &State,
+    launch_id: u64,
+    genesis_allocation: u64,
+) -> Vec<(Principal, Nat)> {
+    let contributions = escrowed_contributions(state, launch_id);
+    let total = contributions
+        .iter()
+        .fold(Nat::from(0u64), |total, contribution| total + contribution.amount.clone());
+
+    if total == Nat::from(0u64) {
+        return Vec::new();
+    }
+
+    contributions
+        .into_iter()
+        .map(|contribution| {
+            let share = Nat::from(genesis_allocation) * contribution.amount / total.clone();
+            (contribution.contributor, share)
+        })
+        .filter(|(_, share)| *share > Nat::from(0u64))
+        .collect()
+}
+
+pub fn genesis_summary(state: &State, launch_id: u64, now: u64) -> Result<GenesisSummary, String> {
+    let (agent_input, closes_at) = match state.pending_launches.get(&launch_id) {
+        Some(launch) => (launch.agent_input, launch.scheduled_at),
+        None => state
+            .launch_records
+            .get(&launch_id)
+            .map(|record| (record.agent_input, record.started_at))
+            .ok_or(String::from(crate::utils::LAUNCH_NOT_FOUND))?,
+    };
+
+    let contributions: Vec<GenesisContribution> = state
+        .genesis_contributions
+        .iter()
+        .map(|(_, contribution)| contribution)
+        .filter(|contribution| contribution.launch_id == launch_id)
+        .collect();
+
+    Ok(GenesisSummary {
+        launch_id,
+        total_contributed: contributions
+            .iter()
+            .fold(Nat::from(0u64), |total, contribution| total + contribution.amount.clone()),
+        contributor_count: contributions.len() as u32,
+        genesis_allocation: genesis_allocation(&agent_input),
+        closes_at,
+        is_open: check_genesis_window(state, launch_id, now).is_ok(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::principal;
+
+    fn shares_of(contributions: &[(u8, u64)], genesis_allocation: u64) -> Vec<(Principal, Nat)> {
+        let mut state = State::new();
+        for (id, amount) in contributions {
+            record_contribution(&mut state, 1, principal(*id), Nat::from(*amount), 0);
+        }
+
+        let mut shares = genesis_shares(&state, 1, genesis_allocation);
+        shares.sort();
+        shares
+    }
+
+    #[test]
+    fn splits_the_allocation_pro_rata() {
+        assert_eq!(
+            shares_of(&[(1, 10), (2, 30)], 1000),
+            vec![(principal(1), Nat::from(250u64)), (principal(2), Nat::from(750u64))]
+        );
+    }
+
+    #[test]
+    fn adds_up_repeated_contributions() {
+        assert_eq!(
+            shares_of(&[(1, 10), (2, 20), (1, 10)], 100),
+            vec![(principal(1), Nat::from(50u64)), (principal(2), Nat::from(50u64))]
+        );
+    }
+
+    #[test]
+    fn rounds_down_and_drops_empty_shares() {
+        // 1000 * 1 / 3001 rounds down to nothing, the remainder stays with the treasury
+        assert_eq!(
+            shares_of(&[(1, 1), (2, 3000)], 1000),
+            vec![(principal(2), Nat::from(999u64))]
+        );
+    }
+
+    #[test]
+    fn has_no_shares_without_contributions() {
+        assert!(shares_of(&[], 1000).is_empty());
+    }
+
+    #[test]
+    fn leaves_out_other_launches_and_settled_contributions() {
+        let mut state = State::new();
+        record_contribution(&mut state, 1, principal(1), Nat::from(10u64), 0);
+        record_contribution(&mut state, 2, principal(2), Nat::from(10u64), 0);
+
+        let key = contribution_key(1, &principal(1));
+        let mut settled = state.genesis_contributions.get(&key).unwrap();
+        settled.contribution_status = LaunchPaymentStatus::Refunded;
+        state.genesis_contributions.insert(key, settled);
+
+        assert!(genesis_shares(&state, 1, 1000).is_empty());
+        assert_eq!(genesis_shares(&state, 2, 1000), vec![(principal(2), Nat::from(1000u64))]);
+    }
+
+    #[test]
+    fn claims_each_contribution_of_a_launch_once() {
+        let mut state = State::new();
+        record_contribution(&mut state, 1, principal(1), Nat::from(10u64), 0);
+        record_contribution(&mut state, 2, principal(2), Nat::from(10u64), 0);
+
+        let claimed = claim_contributions(&mut state, 1, 5);
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].0.contributor, principal(1));
+        assert_eq!(claimed[0].1, 5);
+
+        assert!(claim_contributions(&mut state, 1, 6).is_empty());
+        // a settling contribution no longer counts towards the genesis shares
+        assert!(genesis_shares(&state, 1, 1000).is_empty());
+    }
+}
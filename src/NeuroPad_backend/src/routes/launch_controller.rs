@@ -0,0 +1,348 @@
+use crate::{
+    state_handler::State, LaunchPayment, LaunchPaymentStatus, LaunchRecord, LaunchStatus,
+    LaunchStep, PendingLaunch,
+};
+use candid::{Nat, Principal};
+
+// takes a scheduled launch out of the queue, only its owner can do so and only before launch time
+pub fn cancel_launch_controller(
+    state: &mut State,
+    launch_id: u64,
+    caller: Principal,
+    now: u64,
+) -> Result<PendingLaunch, String> {
+    let launch = state
+        .pending_launches
+        .get(&launch_id)
+        .ok_or(String::from(crate::utils::LAUNCH_NOT_FOUND))?;
+
+    if launch.owner != caller {
+        return Err(String::from("Only the owner of a launch can cancel it."));
+    }
+    if now >= launch.scheduled_at {
+        return Err(String::from("Launch time has passed, the launch can no longer be cancelled."));
+    }
+
+    state.pending_launches.remove(&launch_id);
+
+    Ok(launch)
+}
+
+pub fn pending_launches_of(state: &State, owner: Principal) -> Vec<PendingLaunch> {
+    state
+        .pending_launches
+        .iter()
+        .map(|(_, launch)| launch)
+        .filter(|launch| launch.owner == owner)
+        .collect()
+}
+
+// records that a scheduled launch reached its launch time and starts running its steps
+pub fn start_launch_controller(state: &mut State, launch: PendingLaunch, now: u64) {
+    state.launch_records.insert(
+        launch.launch_id,
+        LaunchRecord {
+            launch_id: launch.launch_id,
+            owner: launch.owner,
+            agent_input: launch.agent_input,
+            launch_status: LaunchStatus::InProgress,
+            last_completed_step: LaunchStep::Scheduled,
+            image_id: None,
+            agent_canister_id: None,
+            ledger_canister_id: None,
+            agent_wasm_version: None,
+            last_error: None,
+            failed_attempts: Some(0),
+            started_at: now,
+            updated_at: now,
+        },
+    );
+}
+
+// marks a failed launch as running again before it is retried or rolled back,
+// controllers can also take over launches that are stuck in progress
+pub fn resume_launch_controller(
+    state: &mut State,
+    launch_id: u64,
+    caller: Principal,
+    caller_is_controller: bool,
+    now: u64,
+) -> Result<LaunchRecord, String> {
+    let mut record = state
+        .launch_records
+        .get(&launch_id)
+        .ok_or(String::from(crate::utils::LAUNCH_NOT_FOUND))?;
+
+    if record.owner != caller && !caller_is_controller {
+        return Err(String::from("Only the owner of a launch can resume it."));
+    }
+
+    if settlement_in_flight(state, launch_id, now) {
+        return Err(String::from(crate::utils::SETTLEMENT_IN_PROGRESS));
+    }
+
+    match record.launch_status {
+        LaunchStatus::Failed => {}
+        LaunchStatus::InProgress if caller_is_controller => {}
+        LaunchStatus::InProgress => return Err(String::from("Launch is still in progress.")),
+        LaunchStatus::Completed | LaunchStatus::RolledBack => {
+            return Err(String::from("Launch is already finished."))
+        }
+    }
+
+    record.launch_status = LaunchStatus::InProgress;
+    record.updated_at = now;
+    state.launch_records.insert(launch_id, record.clone());
+
+    Ok(record)
+}
+
+// stores the outcome of a step, `update` fills in what the step produced;
+// a rollback moves the step back as it removes what the later steps created
+pub fn checkpoint_launch(
+    state: &mut State,
+    launch_id: u64,
+    step: LaunchStep,
+    now: u64,
+    update: impl FnOnce(&mut LaunchRecord),
+) -> Result<LaunchRecord, String> {
+    let mut record = state
+        .launch_records
+        .get(&launch_id)
+        .ok_or(String::from(crate::utils::LAUNCH_NOT_FOUND))?;
+
+    update(&mut record);
+    record.last_completed_step = step;
+    record.updated_at = now;
+    state.launch_records.insert(launch_id, record.clone());
+
+    Ok(record)
+}
+
+// stores how a run of the launch ended and returns the updated record
+pub fn finish_launch_controller(
+    state: &mut State,
+    launch_id: u64,
+    result: Result<LaunchStatus, String>,
+    now: u64,
+) -> Option<LaunchRecord> {
+    let mut record = state.launch_records.get(&launch_id)?;
+
+    match result {
+        Ok(status) => {
+            record.launch_status = status;
+            record.last_error = None;
+        }
+        Err(err) => {
+            record.launch_status = LaunchStatus::Failed;
+            record.last_error = Some(err);
+            record.failed_attempts = Some(record.failed_attempts.unwrap_or(0).saturating_add(1));
+        }
+    }
+    record.updated_at = now;
+    state.launch_records.insert(launch_id, record.clone());
+
+    Some(record)
+}
+
+// settlement of a completed launch can be retried by its owner or a controller
+pub fn completed_launch_of(
+    state: &State,
+    launch_id: u64,
+    caller: Principal,
+    caller_is_controller: bool,
+    now: u64,
+) -> Result<LaunchRecord, String> {
+    let record = state
+        .launch_records
+        .get(&launch_id)
+        .ok_or(String::from(crate::utils::LAUNCH_NOT_FOUND))?;
+
+    if record.owner != caller && !caller_is_controller {
+        return Err(String::from("Only the owner of a launch can settle it."));
+    }
+    if record.launch_status != LaunchStatus::Completed {
+        return Err(String::from("Only completed launches can be settled."));
+    }
+    if settlement_in_flight(state, launch_id, now) {
+        return Err(String::from(crate::utils::SETTLEMENT_IN_PROGRESS));
+    }
+
+    Ok(record)
+}
+
+pub fn stuck_launches(state: &State) -> Vec<LaunchRecord> {
+    state
+        .launch_records
+        .iter()
+        .map(|(_, record)| record)
+        .filter(|record| {
+            record.launch_status == LaunchStatus::InProgress
+                || record.launch_status == LaunchStatus::Failed
+        })
+        .collect()
+}
+
+// claims an escrowed amount before it is transferred so that no other call sends it too,
+// an interrupted settlement is taken over with its first created_at_time;
+// returns the created_at_time of the transfer, None when there is nothing to send
+pub fn claim_escrow(
+    status: &mut LaunchPaymentStatus,
+    settlement_created_at: &mut Option<u64>,
+    now: u64,
+) -> Option<u64> {
+    match status {
+        LaunchPaymentStatus::Escrowed => {
+            *status = LaunchPaymentStatus::Settling;
+            Some(*settlement_created_at.insert(now))
+        }
+        LaunchPaymentStatus::Settling if !settling_since(*settlement_created_at, now) => {
+            Some(*settlement_created_at.get_or_insert(now))
+        }
+        _ => None,
+    }
+}
+
+// a settlement claimed less than SETTLEMENT_TIMEOUT ago is still in flight
+fn settling_since(settlement_created_at: Option<u64>, now: u64) -> bool {
+    settlement_created_at
+        .map(|created_at| now.saturating_sub(created_at) < crate::utils::SETTLEMENT_TIMEOUT)
+        .unwrap_or(false)
+}
+
+// whether a transfer out of escrow of the launch fee or a genesis contribution is in flight
+pub fn settlement_in_flight(state: &State, launch_id: u64, now: u64) -> bool {
+    let in_flight = |status: &LaunchPaymentStatus, settlement_created_at: Option<u64>| {
+        *status == LaunchPaymentStatus::Settling && settling_since(settlement_created_at, now)
+    };
+
+    state
+        .launch_payments
+        .get(&launch_id)
+        .map(|payment| in_flight(&payment.payment_status, payment.settlement_created_at))
+        .unwrap_or(false)
+        || state.genesis_contributions.iter().any(|(_, contribution)| {
+            contribution.launch_id == launch_id
+                && in_flight(&contribution.contribution_status, contribution.settlement_created_at)
+        })
+}
+
+pub fn claim_launch_payment(
+    state: &mut State,
+    launch_id: u64,
+    now: u64,
+) -> Option<(LaunchPayment, u64)> {
+    let mut payment = state.launch_payments.get(&launch_id)?;
+    let created_at_time =
+        claim_escrow(&mut payment.payment_status, &mut payment.settlement_created_at, now)?;
+    state.launch_payments.insert(launch_id, payment.clone());
+
+    Some((payment, created_at_time))
+}
+
+// stores how the transfer of a claimed amount went, a refused transfer leaves it escrowed
+// and a call that may have gone through leaves it claimed until it is sent again
+pub fn finish_escrow(
+    status: &mut LaunchPaymentStatus,
+    settlement_created_at: &mut Option<u64>,
+    settled_block_index: &mut Option<Nat>,
+    result: &Result<Result<Option<Nat>, String>, String>,
+    settled_status: LaunchPaymentStatus,
+) {
+    match result {
+        Ok(Ok(block_index)) => {
+            *status = settled_status;
+            *settled_block_index = block_index.clone();
+        }
+        Ok(Err(_)) => {
+            *status = LaunchPaymentStatus::Escrowed;
+            *settlement_created_at = None;
+        }
+        Err(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: u64 = crate::utils::SETTLEMENT_TIMEOUT;
+
+    #[test]
+    fn claims_escrowed_amounts_once() {
+        let (mut status, mut created_at) = (LaunchPaymentStatus::Escrowed, None);
+
+        assert_eq!(claim_escrow(&mut status, &mut created_at, 10), Some(10));
+        assert_eq!(status, LaunchPaymentStatus::Settling);
+        assert_eq!(claim_escrow(&mut status, &mut created_at, 20), None);
+    }
+
+    #[test]
+    fn takes_over_interrupted_settlements_with_their_created_at() {
+        let (mut status, mut created_at) = (LaunchPaymentStatus::Settling, Some(10));
+
+        assert_eq!(claim_escrow(&mut status, &mut created_at, 10 + TIMEOUT - 1), None);
+        assert_eq!(claim_escrow(&mut status, &mut created_at, 10 + TIMEOUT), Some(10));
+    }
+
+    #[test]
+    fn leaves_settled_amounts_alone() {
+        let (mut status, mut created_at) = (LaunchPaymentStatus::Refunded, Some(10));
+
+        assert_eq!(claim_escrow(&mut status, &mut created_at, 10 + TIMEOUT), None);
+    }
+
+    #[test]
+    fn reports_settlements_in_flight_until_they_time_out() {
+        let mut state = State::new();
+        assert!(!settlement_in_flight(&state, 1, 10));
+
+        state.launch_payments.insert(
+            1,
+            LaunchPayment {
+                launch_id: 1,
+                payer: Principal::anonymous(),
+                amount: Nat::from(100u64),
+                payment_block_index: Nat::from(1u64),
+                payment_status: LaunchPaymentStatus::Escrowed,
+                settled_block_index: None,
+                paid_at: 0,
+                settlement_created_at: None,
+            },
+        );
+        assert!(claim_launch_payment(&mut state, 1, 10).is_some());
+
+        assert!(settlement_in_flight(&state, 1, 10));
+        assert!(!settlement_in_flight(&state, 2, 10));
+        assert!(!settlement_in_flight(&state, 1, 10 + TIMEOUT));
+    }
+
+    #[test]
+    fn keeps_the_claim_only_when_the_transfer_may_have_gone_through() {
+        let settle = |result: Result<Result<Option<Nat>, String>, String>| {
+            let (mut status, mut created_at, mut block_index) =
+                (LaunchPaymentStatus::Settling, Some(10), None);
+            finish_escrow(
+                &mut status,
+                &mut created_at,
+                &mut block_index,
+                &result,
+                LaunchPaymentStatus::Forwarded,
+            );
+            (status, created_at, block_index)
+        };
+
+        assert_eq!(
+            settle(Ok(Ok(Some(Nat::from(7u64))))),
+            (LaunchPaymentStatus::Forwarded, Some(10), Some(Nat::from(7u64)))
+        );
+        assert_eq!(
+            settle(Ok(Err(String::from("refused")))),
+            (LaunchPaymentStatus::Escrowed, None, None)
+        );
+        assert_eq!(
+            settle(Err(String::from("unknown"))),
+            (LaunchPaymentStatus::Settling, Some(10), None)
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use ic_cdk::api::call::{CallResult, RejectionCode};
 use ic_cdk::update;
-use crate::{with_state, ImageData};
+use candid::Principal;
+use crate::{with_state, AssetFileInput, ImageData};
 use crate::guards::*;
 
 type ReturnResult = Result<u32, String>;
@@ -8,6 +9,11 @@ type ReturnResult = Result<u32, String>;
 // upload image
 #[update(guard = prevent_anonymous)] // temp
 pub async fn upload_image(image_data: ImageData) -> Result<String, String> {
+    upload_file_for(image_data, ic_cdk::api::caller()).await
+}
+
+// the asset canister records `owner` as the owner of the file and counts it against their quota
+pub async fn upload_file_for(image_data: ImageData, owner: Principal) -> Result<String, String> {
     let canister_id = with_state(|state| state.canister_data.get(&0));
 
     let canister_id = match canister_id {
@@ -19,7 +25,12 @@ pub async fn upload_image(image_data: ImageData) -> Result<String, String> {
         canister_id.ic_asset_canister,
         // Principal::from_text(canister_id.ic_asset_canister).unwrap(),
         "create_file",
-        (image_data,),
+        (AssetFileInput {
+            content: image_data.content,
+            name: image_data.name,
+            content_type: image_data.content_type,
+            owner: Some(owner),
+        },),
     )
     .await;
     // format!("{:?}", result.ok());
@@ -1,11 +1,51 @@
-use crate::{state_handler::State, ProposalValueStore};
+use crate::{state_handler::State, ProposalState, ProposalValueStore};
+use candid::Principal;
 
-// to record new proposals
+// proposal ids are only unique within one agent canister
+pub fn proposal_store_key(agent_canister_id: &Principal, proposal_id: &str) -> String {
+    format!("{}:{}", agent_canister_id, proposal_id)
+}
+
+// to record new proposals and later state changes of the same proposal
 pub fn add_proposal_controller(
     state: &mut State,
     args: ProposalValueStore,
 ) -> Result<String, String> {
-    state.token_proposal_store.insert(args.proposal_id.clone(), args);
+    if !state
+        .agent_details
+        .contains_key(&args.associated_agent_canister_id)
+    {
+        return Err(String::from(crate::utils::AGENT_NOT_FOUND));
+    }
+
+    let key = proposal_store_key(&args.associated_agent_canister_id, &args.proposal_id);
+    state.token_proposal_store.insert(key, args);
 
     Ok(String::from("Proposal added"))
-}
\ No newline at end of file
+}
+
+// newest proposals first among those matching `filter`
+pub fn list_proposals_controller(
+    state: &State,
+    start: u32,
+    end: u32,
+    filter: impl Fn(&ProposalValueStore) -> bool,
+) -> Vec<ProposalValueStore> {
+    let mut proposals: Vec<ProposalValueStore> = state
+        .token_proposal_store
+        .iter()
+        .map(|(_, proposal)| proposal)
+        .filter(|proposal| filter(proposal))
+        .collect();
+    proposals.sort_by(|a, b| b.proposal_submitted_at.cmp(&a.proposal_submitted_at));
+
+    proposals
+        .into_iter()
+        .skip(start as usize)
+        .take(end.saturating_sub(start) as usize)
+        .collect()
+}
+
+pub fn is_open_proposal(proposal: &ProposalValueStore, now: u64) -> bool {
+    proposal.proposal_status == Some(ProposalState::Open) && now < proposal.proposal_expired_at
+}
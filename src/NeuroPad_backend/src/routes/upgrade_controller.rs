@@ -0,0 +1,126 @@
+use crate::{state_handler::State, AgentUpgradeState, AgentUpgradeStatus};
+use candid::Principal;
+
+// marks the agents that should move to `wasm_version` as pending, returns how many were queued
+pub fn queue_agent_upgrades(
+    state: &mut State,
+    wasm_version: u64,
+    agent_ids: Option<Vec<Principal>>,
+    now: u64,
+) -> Result<u32, String> {
+    if !state.wasm_module.contains_key(&wasm_version) {
+        return Err(String::from(crate::utils::WASM_VERSION_NOT_FOUND));
+    }
+
+    let targets: Vec<Principal> = match agent_ids {
+        Some(agent_ids) => {
+            if let Some(unknown) = agent_ids
+                .iter()
+                .find(|agent_id| !state.agent_details.contains_key(agent_id))
+            {
+                return Err(format!("{} {}", crate::utils::AGENT_NOT_FOUND, unknown));
+            }
+            agent_ids
+        }
+        None => state
+            .agent_details
+            .iter()
+            .filter(|(_, agent)| agent.agent_wasm_version != Some(wasm_version))
+            .map(|(agent_id, _)| agent_id)
+            .collect(),
+    };
+
+    for agent_canister_id in targets.iter() {
+        state.agent_upgrades.insert(
+            *agent_canister_id,
+            AgentUpgradeStatus {
+                agent_canister_id: *agent_canister_id,
+                wasm_version,
+                upgrade_state: AgentUpgradeState::Pending,
+                error: None,
+                updated_at: now,
+            },
+        );
+    }
+
+    Ok(targets.len() as u32)
+}
+
+// takes the next pending and stopped agents out of the queue so that no other batch picks
+// them up, the returned statuses keep the state they were taken in
+pub fn take_upgrade_batch(state: &mut State, batch_size: u32, now: u64) -> Vec<AgentUpgradeStatus> {
+    let batch: Vec<AgentUpgradeStatus> = state
+        .agent_upgrades
+        .iter()
+        .map(|(_, status)| status)
+        .filter(|status| {
+            status.upgrade_state == AgentUpgradeState::Pending
+                || status.upgrade_state == AgentUpgradeState::Stopped
+        })
+        .take(batch_size as usize)
+        .collect();
+
+    for status in batch.iter() {
+        let mut taken = status.clone();
+        taken.upgrade_state = AgentUpgradeState::Upgrading;
+        taken.updated_at = now;
+        state.agent_upgrades.insert(taken.agent_canister_id, taken);
+    }
+
+    batch
+}
+
+// puts agents whose batch did not finish back in the queue, returns whether any agent is queued
+pub fn requeue_interrupted_upgrades(state: &mut State) -> bool {
+    let interrupted: Vec<AgentUpgradeStatus> = state
+        .agent_upgrades
+        .iter()
+        .map(|(_, status)| status)
+        .filter(|status| status.upgrade_state == AgentUpgradeState::Upgrading)
+        .collect();
+
+    for mut status in interrupted {
+        status.upgrade_state = AgentUpgradeState::Pending;
+        state.agent_upgrades.insert(status.agent_canister_id, status);
+    }
+
+    has_queued_upgrades(state, true)
+}
+
+// whether agents are left for a batch, stopped agents are retried along with a rollout
+// but do not keep its batches going on their own
+pub fn has_queued_upgrades(state: &State, with_stopped: bool) -> bool {
+    state.agent_upgrades.iter().any(|(_, status)| {
+        status.upgrade_state == AgentUpgradeState::Pending
+            || (with_stopped && status.upgrade_state == AgentUpgradeState::Stopped)
+    })
+}
+
+// `result` carries the error of starting the agent again once its new code is installed
+pub fn record_agent_upgrade(
+    state: &mut State,
+    mut status: AgentUpgradeStatus,
+    result: Result<Option<String>, String>,
+    now: u64,
+) {
+    match result {
+        Ok(start_error) => {
+            status.upgrade_state = match start_error {
+                Some(_) => AgentUpgradeState::Stopped,
+                None => AgentUpgradeState::Upgraded,
+            };
+            status.error = start_error;
+
+            if let Some(mut agent) = state.agent_details.get(&status.agent_canister_id) {
+                agent.agent_wasm_version = Some(status.wasm_version);
+                state.agent_details.insert(status.agent_canister_id, agent);
+            }
+        }
+        Err(err) => {
+            status.upgrade_state = AgentUpgradeState::Failed;
+            status.error = Some(err);
+        }
+    }
+    status.updated_at = now;
+    state.agent_upgrades.insert(status.agent_canister_id, status);
+}
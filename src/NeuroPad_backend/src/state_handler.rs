@@ -1,8 +1,12 @@
 // use std::collections::HashMap;
 use crate::types::UserProfile;
-use crate::{ CanisterData, AgentDetails, Memory, ProposalValueStore, WasmArgs};
+use crate::{
+    AgentMint, AgentUpgradeStatus, CanisterCycles, CanisterData, CyclesConfig, AgentDetails,
+    GenesisContribution, LaunchPayment, LaunchRecord, Memory, PendingLaunch, ProposalValueStore,
+    WasmArgs, WasmKind,
+};
 use candid::Principal;
-use ic_stable_structures::StableBTreeMap;
+use ic_stable_structures::{StableBTreeMap, StableCell};
 // use std::collections::BTreeMap;
 
 pub struct State {
@@ -14,9 +18,33 @@ pub struct State {
 
     pub token_proposal_store: StableBTreeMap<String, ProposalValueStore, Memory>,
 
-    pub ledger_wasm: Vec<u8>,
+    pub ledger_wasm_module: StableBTreeMap<u64, WasmArgs, Memory>,
+
+    // wasm chunks uploaded so far, keyed by `WasmKind::upload_key`
+    pub wasm_uploads: StableBTreeMap<u8, WasmArgs, Memory>,
+
+    pub cycles_config: StableCell<CyclesConfig, Memory>,
+
+    // keyed by the id of the monitored agent or ledger canister
+    pub canister_cycles: StableBTreeMap<Principal, CanisterCycles, Memory>,
+
+    // keyed by "launch_id:contributor"
+    pub genesis_contributions: StableBTreeMap<String, GenesisContribution, Memory>,
+
+    // keyed by "agent_canister_id:proposal_id"
+    pub agent_mints: StableBTreeMap<String, AgentMint, Memory>,
 
     pub canister_data: StableBTreeMap<u8, CanisterData, Memory>,
+
+    pub launch_payments: StableBTreeMap<u64, LaunchPayment, Memory>,
+
+    pub pending_launches: StableBTreeMap<u64, PendingLaunch, Memory>,
+
+    pub launch_count: StableCell<u64, Memory>,
+
+    pub launch_records: StableBTreeMap<u64, LaunchRecord, Memory>,
+
+    pub agent_upgrades: StableBTreeMap<Principal, AgentUpgradeStatus, Memory>,
 }
 
 impl State {
@@ -25,11 +53,45 @@ impl State {
             user_profile: init_file_contents(),
             agent_details: agent_file_contents(),
             wasm_module: init_wasm_module(),
-            ledger_wasm: vec![],
+            ledger_wasm_module: init_ledger_wasm_module(),
+            wasm_uploads: init_wasm_uploads(),
+            cycles_config: init_cycles_config(),
+            canister_cycles: init_canister_cycles(),
+            genesis_contributions: init_genesis_contributions(),
+            agent_mints: init_agent_mints(),
             canister_data: init_canister_data(),
             token_proposal_store: init_proposal_state(),
+            launch_payments: init_launch_payments(),
+            pending_launches: init_pending_launches(),
+            launch_count: init_launch_count(),
+            launch_records: init_launch_records(),
+            agent_upgrades: init_agent_upgrades(),
         }
     }
+
+    // the newest uploaded agent wasm, new agents are installed with it
+    pub fn latest_agent_wasm(&self) -> Option<(u64, WasmArgs)> {
+        self.wasm_module.last_key_value()
+    }
+
+    pub fn latest_ledger_wasm(&self) -> Option<(u64, WasmArgs)> {
+        self.ledger_wasm_module.last_key_value()
+    }
+
+    pub fn wasm_versions(&self, kind: WasmKind) -> &StableBTreeMap<u64, WasmArgs, Memory> {
+        match kind {
+            WasmKind::Agent => &self.wasm_module,
+            WasmKind::Ledger => &self.ledger_wasm_module,
+        }
+    }
+
+    pub fn next_launch_id(&mut self) -> u64 {
+        let launch_id = self.launch_count.get() + 1;
+        self.launch_count
+            .set(launch_id)
+            .expect("failed to save launch count");
+        launch_id
+    }
 }
 
 fn init_file_contents() -> StableBTreeMap<Principal, UserProfile, Memory> {
@@ -44,6 +106,39 @@ fn init_wasm_module() -> StableBTreeMap<u64, WasmArgs, Memory> {
     StableBTreeMap::init(crate::memory::get_wasm_memory())
 }
 
+fn init_ledger_wasm_module() -> StableBTreeMap<u64, WasmArgs, Memory> {
+    StableBTreeMap::init(crate::memory::get_ledger_wasm_memory())
+}
+
+fn init_wasm_uploads() -> StableBTreeMap<u8, WasmArgs, Memory> {
+    StableBTreeMap::init(crate::memory::get_wasm_upload_memory())
+}
+
+fn init_cycles_config() -> StableCell<CyclesConfig, Memory> {
+    StableCell::init(
+        crate::memory::get_cycles_config_memory(),
+        CyclesConfig {
+            min_cycles: crate::utils::DEFAULT_MIN_CYCLES,
+            top_up_amount: crate::utils::DEFAULT_TOP_UP_CYCLES,
+            check_interval_seconds: crate::utils::DEFAULT_CYCLES_CHECK_INTERVAL,
+            backend_reserve: crate::utils::DEFAULT_BACKEND_CYCLES_RESERVE,
+        },
+    )
+    .expect("failed to init cycles config")
+}
+
+fn init_canister_cycles() -> StableBTreeMap<Principal, CanisterCycles, Memory> {
+    StableBTreeMap::init(crate::memory::get_canister_cycles_memory())
+}
+
+fn init_genesis_contributions() -> StableBTreeMap<String, GenesisContribution, Memory> {
+    StableBTreeMap::init(crate::memory::get_genesis_contribution_memory())
+}
+
+fn init_agent_mints() -> StableBTreeMap<String, AgentMint, Memory> {
+    StableBTreeMap::init(crate::memory::get_agent_mint_memory())
+}
+
 fn init_canister_data() -> StableBTreeMap<u8, CanisterData, Memory> {
     StableBTreeMap::init(crate::memory::get_canister_data_memory())
 }
@@ -52,6 +147,27 @@ fn init_proposal_state() -> StableBTreeMap<String, ProposalValueStore, Memory> {
     StableBTreeMap::init(crate::memory::get_token_stack_memory())
 }
 
+fn init_launch_payments() -> StableBTreeMap<u64, LaunchPayment, Memory> {
+    StableBTreeMap::init(crate::memory::get_launch_payment_memory())
+}
+
+fn init_pending_launches() -> StableBTreeMap<u64, PendingLaunch, Memory> {
+    StableBTreeMap::init(crate::memory::get_pending_launch_memory())
+}
+
+fn init_launch_count() -> StableCell<u64, Memory> {
+    StableCell::init(crate::memory::get_launch_count_memory(), 0)
+        .expect("failed to init launch count")
+}
+
+fn init_launch_records() -> StableBTreeMap<u64, LaunchRecord, Memory> {
+    StableBTreeMap::init(crate::memory::get_launch_record_memory())
+}
+
+fn init_agent_upgrades() -> StableBTreeMap<Principal, AgentUpgradeStatus, Memory> {
+    StableBTreeMap::init(crate::memory::get_agent_upgrade_memory())
+}
+
 impl Default for State {
     fn default() -> Self {
         State::new()
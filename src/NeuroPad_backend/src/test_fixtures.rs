@@ -0,0 +1,56 @@
+// builders shared by the unit tests of the controllers
+use crate::types::{AgentInput, AgentType, TokenConfig, VestingAllocation};
+use candid::Principal;
+
+pub fn principal(id: u8) -> Principal {
+    Principal::from_slice(&[id])
+}
+
+// allocations adding up to the 1000 tokens supplied by `agent_input`
+pub fn token_config() -> TokenConfig {
+    TokenConfig {
+        decimals: 8,
+        transfer_fee: 10,
+        use_image_as_logo: false,
+        fee_collector: None,
+        treasury_allocation: 600,
+        members_allocation: 200,
+        creator_allocation: 100,
+        vesting_reserved: 100,
+        vesting: vec![VestingAllocation {
+            beneficiary: principal(1),
+            amount: 100,
+            cliff_seconds: 10,
+            vesting_seconds: 100,
+        }],
+        genesis_allocation: 0,
+    }
+}
+
+// a standard launch with `principal(1)` as its only member
+pub fn agent_input(token_config: Option<TokenConfig>) -> AgentInput {
+    AgentInput {
+        agent_name: String::from("agent"),
+        agent_category: String::new(),
+        agent_type: AgentType::StandardLaunch,
+        agent_overview: String::new(),
+        members: vec![principal(1)],
+        agent_website: String::new(),
+        agent_twitter: String::new(),
+        agent_discord: String::new(),
+        agent_telegram: String::new(),
+        token_name: String::from("Agent"),
+        token_symbol: String::from("AGT"),
+        token_supply: 1000,
+        agent_description: String::new(),
+        image_id: String::new(),
+        image_content: serde_bytes::ByteBuf::new(),
+        image_content_type: String::from("image/png"),
+        image_title: String::new(),
+        agent_lunch_time: 0,
+        image_canister: Principal::anonymous(),
+        members_count: 1,
+        proposal_entry: None,
+        token_config,
+    }
+}
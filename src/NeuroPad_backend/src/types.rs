@@ -15,6 +15,8 @@ pub type CanisterId = Principal;
 pub struct ProposalPlace {
     pub place_name : String,
     pub min_required_thredshold : u64,
+    pub quorum_percentage : u64,
+    pub approval_percentage : u64,
 }
 
 #[derive(
@@ -47,6 +49,15 @@ pub struct ImageData {
     pub content_type: String,
 }
 
+// create_file input of ic_asset_handler, the backend uploads as a manager on behalf of `owner`
+#[derive(Clone, CandidType, Serialize, Deserialize)]
+pub struct AssetFileInput {
+    pub content: ByteBuf,
+    pub name: String,
+    pub content_type: String,
+    pub owner: Option<Principal>,
+}
+
 
 #[derive(
     CandidType, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Default,
@@ -562,6 +573,64 @@ pub struct AgentInput {
     pub agent_lunch_time : u64,
     pub image_canister: Principal,
     pub members_count: u32,
+    pub proposal_entry: Option<Vec<ProposalPlace>>,
+    // without it the whole supply goes to the agent treasury with no transfer fee
+    pub token_config: Option<TokenConfig>,
+}
+
+// ledger settings of the agent token, allocations are in the smallest token unit
+// and have to add up to the token supply
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
+pub struct TokenConfig {
+    pub decimals: u8,
+    pub transfer_fee: u64,
+    // publishes the agent image as the icrc1:logo of the token
+    pub use_image_as_logo: bool,
+    pub fee_collector: Option<Principal>,
+    pub treasury_allocation: u64,
+    // split evenly between the members, the remainder stays in the treasury
+    pub members_allocation: u64,
+    pub creator_allocation: u64,
+    // minted to the vesting subaccounts of the agent canister, split by `vesting`
+    pub vesting_reserved: u64,
+    pub vesting: Vec<VestingAllocation>,
+    // shared pro-rata between the contributors of a genesis launch
+    pub genesis_allocation: u64,
+}
+
+// tokens released to the beneficiary linearly over `vesting_seconds` from the launch,
+// nothing can be claimed before the cliff
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
+pub struct VestingAllocation {
+    pub beneficiary: Principal,
+    pub amount: u64,
+    pub cliff_seconds: u64,
+    pub vesting_seconds: u64,
+}
+
+// NeuroPad tokens a user committed to a genesis launch, escrowed by the backend
+// until the launch settles like the launch fee
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
+pub struct GenesisContribution {
+    pub launch_id: u64,
+    pub contributor: Principal,
+    pub amount: Nat,
+    pub contribution_status: LaunchPaymentStatus,
+    pub settled_block_index: Option<Nat>,
+    pub last_contributed_at: u64,
+    // same as the settlement_created_at of the launch payment
+    pub settlement_created_at: Option<u64>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
+pub struct GenesisSummary {
+    pub launch_id: u64,
+    pub total_contributed: Nat,
+    pub contributor_count: u32,
+    pub genesis_allocation: u64,
+    // contributions are accepted until the launch time of the agent
+    pub closes_at: u64,
+    pub is_open: bool,
 }
 
 #[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
@@ -583,6 +652,27 @@ pub struct AgentCanisterInput {
     pub agent_description: String,
     pub agent_lunch_time : u64,
     pub members_count: u32,
+    pub proposal_entry: Option<Vec<ProposalPlace>>,
+    pub vesting: Option<Vec<VestingAllocation>>,
+}
+
+// sent by an agent canister to mint its own token after an accepted mint proposal
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
+pub struct MintRequest {
+    pub proposal_id: String,
+    pub amount: Nat,
+    // fixed by the agent on the first attempt so the ledger deduplicates retried mints
+    pub created_at_time: Option<u64>,
+}
+
+// a mint made for an agent proposal, a repeated request returns the recorded block
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
+pub struct AgentMint {
+    pub agent_canister_id: Principal,
+    pub proposal_id: String,
+    pub amount: Nat,
+    pub block_index: Nat,
+    pub minted_at: u64,
 }
 
 #[derive(Clone, CandidType, Serialize, Deserialize)]
@@ -605,6 +695,9 @@ pub struct  AgentDetails {
     pub token_name: String,
     pub agent_description: String,
     pub agent_lunch_time : u64,
+    // version of the agent wasm the canister runs, a key of `wasm_module`,
+    // None for agents launched with the baseline wasm before versions were tracked
+    pub agent_wasm_version: Option<u64>,
 }
 
 #[derive(Clone, CandidType, Serialize, Deserialize)]
@@ -670,6 +763,99 @@ pub struct LedgerCanisterId {
 #[derive(CandidType, Serialize, Deserialize)]
 pub struct WasmArgs {
     pub wasm: Vec<u8>,
+    // sha256 of `wasm`, checked against the hash given when the upload is committed,
+    // None while uploading and on modules stored before hashes were kept
+    pub wasm_module_hash: Option<Vec<u8>>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum WasmKind {
+    Agent,
+    Ledger,
+}
+
+impl WasmKind {
+    // key of the upload in progress for this kind of wasm
+    pub fn upload_key(&self) -> u8 {
+        match self {
+            WasmKind::Agent => 0,
+            WasmKind::Ledger => 1,
+        }
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
+pub struct WasmVersion {
+    pub version: u64,
+    pub size: u64,
+    #[serde(with = "serde_bytes")]
+    pub wasm_module_hash: Vec<u8>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum AgentUpgradeState {
+    Pending,
+    // taken by a running batch
+    Upgrading,
+    Upgraded,
+    // upgraded but could not be started again, the next batch only starts it
+    Stopped,
+    Failed,
+}
+
+// rollout status of an agent wasm version on one agent canister
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
+pub struct AgentUpgradeStatus {
+    pub agent_canister_id: Principal,
+    pub wasm_version: u64,
+    pub upgrade_state: AgentUpgradeState,
+    pub error: Option<String>,
+    pub updated_at: u64,
+}
+
+// thresholds of the cycles monitor, amounts are in cycles
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
+pub struct CyclesConfig {
+    pub min_cycles: u64,
+    pub top_up_amount: u64,
+    pub check_interval_seconds: u64,
+    // the backend stops topping up once its own balance would drop below this
+    pub backend_reserve: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum MonitoredCanister {
+    Agent,
+    Ledger,
+}
+
+// latest cycles check of an agent or ledger canister
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
+pub struct CanisterCycles {
+    pub canister_id: Principal,
+    pub agent_canister_id: Principal,
+    pub canister_kind: MonitoredCanister,
+    pub cycles: Nat,
+    pub idle_cycles_burned_per_day: Nat,
+    pub topped_up_total: u64,
+    pub last_top_up_at: Option<u64>,
+    pub last_error: Option<String>,
+    pub checked_at: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
+pub struct AgentCyclesReport {
+    pub agent_canister_id: Principal,
+    pub agent: Option<CanisterCycles>,
+    pub ledger: Option<CanisterCycles>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
+pub struct UpgradeAgentsArgs {
+    pub wasm_version: u64,
+    // every agent that does not run `wasm_version` yet when not set
+    pub agent_ids: Option<Vec<Principal>>,
+    pub batch_size: Option<u32>,
 }
 
 #[derive(CandidType, Deserialize, Debug)]
@@ -678,6 +864,7 @@ pub struct InitialArgs {
     pub ic_asset_canister_id: Principal,
     pub agent_canister_id: Principal,
     pub neuropad_ledger_id: Principal,
+    pub launch_fee: u64,
 }
 
 // LEDGER PARAMS
@@ -699,6 +886,86 @@ pub struct CanisterData {
     pub agent_canister: Principal,
     pub paymeny_recipient: Principal,
     pub neuropad_ledger_id: Principal,
+    // NeuroPad tokens charged for launching an agent, None on data stored before the fee existed
+    pub launch_fee: Option<u64>,
+}
+
+// an agent waiting for its launch time
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
+pub struct PendingLaunch {
+    pub launch_id: u64,
+    pub owner: Principal,
+    pub agent_input: AgentInput,
+    pub scheduled_at: u64,
+    pub created_at: u64,
+}
+
+// steps of an agent launch in the order they run, a launch records the last one it completed
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LaunchStep {
+    Scheduled,
+    ImageUploaded,
+    AgentCanisterCreated,
+    AgentCyclesDeposited,
+    AgentCodeInstalled,
+    LedgerCanisterCreated,
+    LedgerCyclesDeposited,
+    LedgerCodeInstalled,
+    LedgerLinked,
+    Registered,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum LaunchStatus {
+    InProgress,
+    // stopped at a step, can be retried or rolled back
+    Failed,
+    Completed,
+    RolledBack,
+}
+
+// checkpoint of a launch, written after every completed step
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
+pub struct LaunchRecord {
+    pub launch_id: u64,
+    pub owner: Principal,
+    pub agent_input: AgentInput,
+    pub launch_status: LaunchStatus,
+    pub last_completed_step: LaunchStep,
+    pub image_id: Option<String>,
+    pub agent_canister_id: Option<Principal>,
+    pub ledger_canister_id: Option<Principal>,
+    pub agent_wasm_version: Option<u64>,
+    pub last_error: Option<String>,
+    // None on records stored before failed runs were counted
+    pub failed_attempts: Option<u32>,
+    pub started_at: u64,
+    pub updated_at: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum LaunchPaymentStatus {
+    // held by the backend until the launch finishes
+    Escrowed,
+    // claimed by a transfer out of escrow that has not come back yet
+    Settling,
+    Forwarded,
+    Refunded,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
+pub struct LaunchPayment {
+    pub launch_id: u64,
+    pub payer: Principal,
+    pub amount: Nat,
+    pub payment_block_index: Nat,
+    pub payment_status: LaunchPaymentStatus,
+    // block of the transfer to the payment recipient or of the refund to the payer
+    pub settled_block_index: Option<Nat>,
+    pub paid_at: u64,
+    // set when the payment is claimed for settlement, reused as the ledger created_at_time
+    // when an interrupted settlement is sent again
+    pub settlement_created_at: Option<u64>,
 }
 
 #[derive(CandidType, Serialize, Deserialize, Debug)]
@@ -749,20 +1016,44 @@ pub struct ProposalKeyStore {
     pub proposal_id: String,
 }
 
+// mirrors the proposal states of the agent canister
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ProposalState {
+    Open,
+    Accepted,
+    Rejected,
+    Executing,
+    Succeeded,
+    Expired,
+    Unreachable,
+}
+
+// summary of an agent proposal, pushed by the agent canister on creation and on every state change
 #[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
 pub struct ProposalValueStore {
     pub associated_agent_canister_id: Principal,
     pub proposal_id: String,
     pub propsal_title: String,
     pub proposal_description: String,
+    // None on proposals stored before agents reported their state
+    pub proposal_status: Option<ProposalState>,
     pub proposal_submitted_at: u64,
     pub proposal_expired_at: u64,
-    pub required_votes: u32,
     pub created_by: Principal,
-    pub principal_action: Principal,
-    pub agent_members: Vec<Principal>,
-    pub minimum_threadsold : u64,
+    pub proposal_entry: Option<String>,
+    // only set on proposals stored before agent canisters synced their own proposals
+    pub required_votes: Option<u32>,
+    pub principal_action: Option<Principal>,
+    pub agent_members: Option<Vec<Principal>>,
+    pub minimum_threadsold: Option<u64>,
 }
+
+#[derive(CandidType, Serialize, Deserialize)]
+pub struct Pagination {
+    pub start: u32,
+    pub end: u32,
+}
+
 const MAX_VALUE_SIZE_CANISTER_DATA: u32 = 600;
 
 impl Storable for UserProfile {
@@ -824,6 +1115,102 @@ impl Storable for ProposalValueStore {
     const BOUND: Bound = Bound::Unbounded;
 }
 
+impl Storable for PendingLaunch {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for AgentMint {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for GenesisContribution {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for CyclesConfig {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for CanisterCycles {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for AgentUpgradeStatus {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for LaunchRecord {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for LaunchPayment {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
 impl Storable for CanisterData {
     fn to_bytes(&self) -> Cow<[u8]> {
         Cow::Owned(Encode!(self).unwrap())
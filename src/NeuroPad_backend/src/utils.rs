@@ -12,4 +12,38 @@ pub const CREATE_AGENT_CANISTER_FAIL: &str = "Failed to create agent canister";
 pub const INTER_CANISTER_FAILED: &str = "Failed to perform inter canister call. ";
 pub const ERROR_ANALYTICS: &str = "Analytics data is corrupted or not found";
 pub const SUCCESS_PROPOSAL: &str = "Proposal is successfully created. ";
-pub const CREATE_CANISTER_FAIL: &str = "Failed to create canister: ";
\ No newline at end of file
+pub const CREATE_CANISTER_FAIL: &str = "Failed to create canister: ";
+pub const AGENT_NOT_FOUND: &str = "Agent not found on the platform.";
+pub const NOT_A_CONTROLLER: &str = "Only controllers of the platform are allowed !";
+pub const LAUNCH_PAYMENT_FAILED: &str = "Failed to collect the launch fee: ";
+pub const LAUNCH_NOT_FOUND: &str = "Pending launch not found.";
+pub const SETTLEMENT_IN_PROGRESS: &str = "Settlement of this launch is still in progress.";
+
+// cycles sent to the canisters of a launched agent on top of the creation cycles
+pub const AGENT_CANISTER_CYCLES: u128 = 300_000_000_000;
+pub const LEDGER_CANISTER_CYCLES: u128 = 150_000_000_000;
+
+pub const FIRST_WASM_VERSION: u64 = 1;
+pub const DEFAULT_UPGRADE_BATCH_SIZE: u32 = 10;
+// a launch that failed this many times is rolled back and refunded
+pub const MAX_LAUNCH_ATTEMPTS: u32 = 3;
+// a transfer out of escrow still Settling after this long was interrupted and may be sent
+// again, well within the 24 hours in which the ledger deduplicates it
+pub const SETTLEMENT_TIMEOUT: u64 = 10 * 60 * 1_000_000_000;
+pub const WASM_VERSION_NOT_FOUND: &str = "Agent wasm version not found.";
+pub const WASM_NOT_UPLOADED: &str = "Wasm module is not uploaded yet.";
+// a module whose size with its init arg is above this is installed from the chunk store
+// of the target canister
+pub const MAX_INSTALL_CODE_SIZE: usize = 2_000_000;
+// largest chunk the management canister chunk store accepts
+pub const WASM_CHUNK_SIZE: usize = 1024 * 1024;
+pub const DEFAULT_MIN_CYCLES: u64 = 100_000_000_000;
+pub const DEFAULT_TOP_UP_CYCLES: u64 = 200_000_000_000;
+pub const DEFAULT_CYCLES_CHECK_INTERVAL: u64 = 6 * 60 * 60;
+pub const DEFAULT_BACKEND_CYCLES_RESERVE: u64 = 2_000_000_000_000;
+pub const MAX_TOKEN_DECIMALS: u8 = 18;
+// the logo is sent inline as a data url in the ledger init args
+pub const MAX_LOGO_SIZE: usize = 256 * 1024;
+pub const LEDGER_NUM_BLOCKS_TO_ARCHIVE: u64 = 1000;
+pub const LEDGER_ARCHIVE_TRIGGER_THRESHOLD: u64 = 2000;
+pub const GENESIS_WINDOW_CLOSED: &str = "Contribution window of this launch is closed.";
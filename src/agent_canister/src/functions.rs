@@ -1,7 +1,14 @@
 mod proposal_functions;
+mod bounty_functions;
+mod poll_functions;
+mod comment_functions;
+mod sale_functions;
+mod vesting_functions;
+mod staking_functions;
 use candid::{CandidType, Principal};
 use ic_cdk::api::call::{CallResult, RejectionCode};
 mod ledger_functions;
+pub use ledger_functions::*;
 
 use serde::Serialize;
 
@@ -0,0 +1,28 @@
+use crate::proposal_route::{claim_bounty_controller, list_bounties_controller, settle_bounty};
+use crate::types::{Bounty, BountyStatus, Pagination};
+use crate::{guards::*, with_state};
+use candid::Nat;
+use ic_cdk::{api, query, update};
+
+// any contributor can claim, a claim locks the bounty until it is completed or expires
+#[update(guard=prevent_anonymous)]
+fn claim_bounty(bounty_id: String) -> Result<(), String> {
+    with_state(|state| claim_bounty_controller(state, &bounty_id, api::caller(), api::time()))
+}
+
+// returns the escrow of a bounty that was not done in time back to the treasury
+#[update(guard=check_members)]
+async fn refund_expired_bounty(bounty_id: String) -> Result<Nat, String> {
+    settle_bounty(&bounty_id, BountyStatus::Refunded).await
+}
+
+#[query(guard=prevent_anonymous)]
+fn get_bounty(bounty_id: String) -> Result<Bounty, String> {
+    with_state(|state| state.bounties.get(&bounty_id))
+        .ok_or(String::from(crate::utils::BOUNTY_NOT_FOUND))
+}
+
+#[query(guard=prevent_anonymous)]
+fn list_bounties(page: Pagination) -> Vec<Bounty> {
+    with_state(|state| list_bounties_controller(state, page.start, page.end))
+}
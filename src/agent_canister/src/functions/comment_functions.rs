@@ -0,0 +1,29 @@
+use crate::proposal_route::{
+    comment_on_proposal_controller, get_comments_controller, like_comment_controller,
+    reply_to_comment_controller,
+};
+use crate::types::{Comment, CommentLikeArgs, Pagination, ReplyCommentArgs};
+use crate::{guards::*, with_state};
+use ic_cdk::{api, query, update};
+
+#[update(guard=prevent_anonymous)]
+fn comment_on_proposal(proposal_id: String, comment_text: String) -> Result<String, String> {
+    with_state(|state| {
+        comment_on_proposal_controller(state, proposal_id, comment_text, api::caller(), api::time())
+    })
+}
+
+#[update(guard=prevent_anonymous)]
+fn reply_to_comment(args: ReplyCommentArgs) -> Result<(), String> {
+    with_state(|state| reply_to_comment_controller(state, args, api::caller(), api::time()))
+}
+
+#[update(guard=prevent_anonymous)]
+fn like_comment(args: CommentLikeArgs) -> Result<u16, String> {
+    with_state(|state| like_comment_controller(state, args, api::caller()))
+}
+
+#[query(guard=prevent_anonymous)]
+fn get_comments(proposal_id: String, page: Pagination) -> Vec<Comment> {
+    with_state(|state| get_comments_controller(state, &proposal_id, page.start, page.end))
+}
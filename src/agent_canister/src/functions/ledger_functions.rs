@@ -1,14 +1,108 @@
-use candid::Principal;
-use ic_cdk::update;
+use candid::{Nat, Principal};
 use icrc_ledger_types::{
-    icrc1::{account::Account, transfer::BlockIndex},
+    icrc1::{
+        account::{Account, Subaccount},
+        transfer::{BlockIndex, Memo, TransferArg, TransferError},
+    },
     icrc2::transfer_from::{TransferFromArgs, TransferFromError},
 };
 
 use crate::TokenTransferArgs;
 
+pub async fn icrc1_fee(ledger_canister_id: Principal) -> Result<u64, String> {
+    let (fee,) = ic_cdk::call::<(), (Nat,)>(ledger_canister_id, "icrc1_fee", ())
+        .await
+        .map_err(|e| format!("failed to call ledger: {:?}", e))?;
 
-#[update]
+    u64::try_from(&fee.0).map_err(|_| String::from("ledger fee does not fit in u64"))
+}
+
+// moves tokens held by this agent canister (optionally from one of its subaccounts),
+// with a fixed `created_at_time` a retried transfer is deduplicated by the ledger
+// and resolves to the block of the first one
+pub async fn icrc1_transfer(
+    ledger_canister_id: Principal,
+    from_subaccount: Option<Subaccount>,
+    to: Account,
+    tokens: u64,
+    memo: Option<Vec<u8>>,
+    created_at_time: Option<u64>,
+) -> Result<BlockIndex, String> {
+    send_icrc1_transfer(ledger_canister_id, from_subaccount, to, tokens, memo, created_at_time)
+        .await
+        .and_then(|sent| sent)
+}
+
+// same as `icrc1_transfer`, the inner error is a transfer the ledger refused and the outer
+// one a failed call the transfer may have gone through with
+pub async fn send_icrc1_transfer(
+    ledger_canister_id: Principal,
+    from_subaccount: Option<Subaccount>,
+    to: Account,
+    tokens: u64,
+    memo: Option<Vec<u8>>,
+    created_at_time: Option<u64>,
+) -> Result<Result<BlockIndex, String>, String> {
+    let transfer_args = TransferArg {
+        from_subaccount,
+        to,
+        fee: None,
+        created_at_time,
+        memo: memo.map(Memo::from),
+        amount: tokens.into(),
+    };
+
+    let result = ic_cdk::call::<(TransferArg,), (Result<BlockIndex, TransferError>,)>(
+        ledger_canister_id,
+        "icrc1_transfer",
+        (transfer_args,),
+    )
+    .await
+    .map_err(|e| format!("failed to call ledger: {:?}", e))?
+    .0;
+
+    match result {
+        Ok(block_index) => Ok(Ok(block_index)),
+        Err(TransferError::Duplicate { duplicate_of }) => Ok(Ok(duplicate_of)),
+        // past the deduplication window an earlier attempt can no longer be told apart
+        Err(TransferError::TooOld) => Err(String::from("ledger transfer error TooOld")),
+        Err(e) => Ok(Err(format!("ledger transfer error {:?}", e))),
+    }
+}
+
+// pulls tokens `from` approved for this agent canister into any account, on any ledger
+pub async fn icrc2_transfer_from(
+    ledger_canister_id: Principal,
+    from: Principal,
+    to: Account,
+    tokens: u64,
+    memo: Option<Vec<u8>>,
+) -> Result<BlockIndex, String> {
+    let transfer_args = TransferFromArgs {
+        amount: tokens.into(),
+        to,
+        fee: None,
+        memo: memo.map(Memo::from),
+        created_at_time: None,
+        spender_subaccount: None,
+        from: Account {
+            owner: from,
+            subaccount: None,
+        },
+    };
+
+    ic_cdk::call::<(TransferFromArgs,), (Result<BlockIndex, TransferFromError>,)>(
+        ledger_canister_id,
+        "icrc2_transfer_from",
+        (transfer_args,),
+    )
+    .await
+    .map_err(|e| format!("failed to call ledger: {:?}", e))?
+    .0
+    .map_err(|e| format!("ledger transfer error {:?}", e))
+}
+
+// pulls tokens the `from` principal approved for this agent canister
 pub async fn icrc_transfer(ledger_canister_id : Principal , args: TokenTransferArgs) -> Result<BlockIndex, String> {
     let transfer_args = TransferFromArgs {
         amount: args.tokens.into(),
@@ -0,0 +1,43 @@
+use crate::proposal_route::{
+    create_poll_controller, head_count, list_polls_controller, poll_result,
+    vote_on_poll_controller, voting_power_snapshot,
+};
+use crate::types::{CreatePoll, Pagination, PollResult};
+use crate::{guards::*, with_state};
+use ic_cdk::{api, query, update};
+
+#[update(guard=check_members)]
+fn create_poll(args: CreatePoll) -> Result<String, String> {
+    let created_by = api::caller();
+    // only a token-weighted poll needs the stakes, so a head-counted one also runs before the
+    // agent has a token ledger
+    let voting_power = with_state(|state| {
+        let members = state.agent().members.clone();
+        if args.token_weighted {
+            voting_power_snapshot(state, members, api::time())
+        } else {
+            Ok(head_count(members))
+        }
+    })?;
+
+    with_state(|state| create_poll_controller(state, args, created_by, voting_power, api::time()))
+}
+
+#[update(guard=check_members)]
+fn vote_on_poll(poll_id: String, option_id: String) -> Result<(), String> {
+    with_state(|state| {
+        vote_on_poll_controller(state, &poll_id, &option_id, api::caller(), api::time())
+    })
+}
+
+#[query(guard=prevent_anonymous)]
+fn get_poll(poll_id: String) -> Result<PollResult, String> {
+    with_state(|state| state.polls.get(&poll_id))
+        .map(|poll| poll_result(poll, api::time()))
+        .ok_or(String::from(crate::utils::POLL_NOT_FOUND))
+}
+
+#[query(guard=prevent_anonymous)]
+fn list_polls(page: Pagination) -> Vec<PollResult> {
+    with_state(|state| list_polls_controller(state, page.start, page.end, api::time()))
+}
@@ -1,13 +1,22 @@
-use crate::types::AgentDetails;
+use crate::proposal_route::{
+    create_proposal_controller, is_group_member, get_proposal_controller, list_proposals_controller,
+    run_accepted_proposal, start_proposal_checker, sync_proposal_with_backend, validate_sale_config,
+    vote_controller, voting_power_snapshot,
+};
+use crate::types::{
+    AddMemberArgs, AgentDetails, BountyDone, BountyRaised, BountyStatus, ConfigureSaleArgs,
+    CreateGeneralPurpose, MintTokenArgs, Pagination, ProposalInput, ProposalState, ProposalType,
+    Proposals, RemoveMemberArgs, TokenTransferPolicy, VoteParam,
+};
 use crate::{guards::*, with_state};
 use candid::Principal;
-use ic_cdk::{query, update};
+use ic_cdk::{api, query, update};
 use std::collections::HashSet;
 
 #[query(guard=prevent_anonymous)]
 async fn get_agent_detail() -> AgentDetails {
     with_state(|state| {
-        let mut agent = state.agent.clone();
+        let mut agent = state.agent().clone();
         let unique_members: HashSet<candid::Principal> = agent.members.iter().cloned().collect();
         agent.members = unique_members.into_iter().collect();
         agent.members_count = agent.members.len() as u32;
@@ -15,9 +24,211 @@ async fn get_agent_detail() -> AgentDetails {
     })
 }
 
-#[update(guard=prevent_anonymous)]
+// the ledger decides voting power and holds the treasury, so only the launching canister may set it
+#[update(guard=is_controller)]
 fn add_ledger_canister_id(id: Principal) -> Result<(), String> {
-    with_state(|state| state.agent.token_ledger_id = id);
+    with_state(|state| state.update_agent(|agent| agent.token_ledger_id = id));
 
     Ok(())
-}
\ No newline at end of file
+}
+
+// snapshots the members' staked voting power and records the proposal
+async fn submit_proposal(proposal: ProposalInput) -> Result<String, String> {
+    let created_by = api::caller();
+    let voting_power = with_state(|state| {
+        voting_power_snapshot(state, state.agent().members.clone(), api::time())
+    })?;
+
+    let proposal_id = with_state(|state| {
+        create_proposal_controller(state, proposal, created_by, voting_power, api::time())
+    })?;
+
+    let expired_at = with_state(|state| state.proposals.get(&proposal_id))
+        .map(|proposal| proposal.proposal_expired_at)
+        .unwrap_or_default();
+    start_proposal_checker(proposal_id.clone(), expired_at);
+    sync_proposal_with_backend(&proposal_id).await;
+
+    Ok(proposal_id)
+}
+
+#[update(guard=check_members)]
+async fn create_proposal(args: CreateGeneralPurpose) -> Result<String, String> {
+    submit_proposal(ProposalInput {
+        proposal_title: args.proposal_title,
+        proposal_description: args.description,
+        proposal_type: ProposalType::GeneralPurpose,
+        proposal_entry: args.proposal_entry,
+        proposal_expired_at: None,
+    })
+    .await
+}
+
+#[update(guard=check_members)]
+async fn proposal_to_transfer_token(args: TokenTransferPolicy) -> Result<String, String> {
+    if args.tokens == 0 {
+        return Err(String::from("Tokens to transfer must be greater than zero !"));
+    }
+    if args.to == Principal::anonymous() {
+        return Err(String::from(crate::utils::WARNING_ANONYMOUS_CALL));
+    }
+
+    submit_proposal(ProposalInput {
+        proposal_title: format!("Transfer {} tokens to {}", args.tokens, args.to),
+        proposal_description: args.description.clone(),
+        proposal_entry: args.proposal_entry.clone(),
+        proposal_type: ProposalType::TokenTransfer(args),
+        proposal_expired_at: None,
+    })
+    .await
+}
+
+#[update(guard=check_members)]
+async fn proposal_to_add_member_to_group(args: AddMemberArgs) -> Result<String, String> {
+    if args.group_name.trim().is_empty() {
+        return Err(String::from("Group name cannot be empty !"));
+    }
+    if args.new_member == Principal::anonymous() {
+        return Err(String::from(crate::utils::WARNING_ANONYMOUS_CALL));
+    }
+    if with_state(|state| is_group_member(state.agent(), &args.group_name, &args.new_member)) {
+        return Err(format!("{} is already a member of {}", args.new_member, args.group_name));
+    }
+
+    submit_proposal(ProposalInput {
+        proposal_title: format!("Add {} to {}", args.new_member, args.group_name),
+        proposal_description: args.description.clone(),
+        proposal_entry: args.proposal_entry.clone(),
+        proposal_type: ProposalType::AddMember(args),
+        proposal_expired_at: None,
+    })
+    .await
+}
+
+#[update(guard=check_members)]
+async fn proposal_to_remove_member_from_group(args: RemoveMemberArgs) -> Result<String, String> {
+    if !with_state(|state| is_group_member(state.agent(), &args.group_name, &args.action_member)) {
+        return Err(format!("{} is not a member of {}", args.action_member, args.group_name));
+    }
+
+    submit_proposal(ProposalInput {
+        proposal_title: format!("Remove {} from {}", args.action_member, args.group_name),
+        proposal_description: args.description.clone(),
+        proposal_entry: args.proposal_entry.clone(),
+        proposal_type: ProposalType::RemoveMember(args),
+        proposal_expired_at: None,
+    })
+    .await
+}
+
+#[update(guard=check_members)]
+async fn proposal_to_bounty_raised(args: BountyRaised) -> Result<String, String> {
+    if args.tokens == 0 {
+        return Err(String::from("Bounty tokens must be greater than zero !"));
+    }
+    if args.bounty_task.trim().is_empty() {
+        return Err(String::from("Bounty task cannot be empty !"));
+    }
+
+    submit_proposal(ProposalInput {
+        proposal_title: format!("Bounty of {} tokens: {}", args.tokens, args.bounty_task),
+        proposal_description: args.description.clone(),
+        proposal_entry: args.proposal_entry.clone(),
+        proposal_type: ProposalType::BountyRaised(args),
+        proposal_expired_at: None,
+    })
+    .await
+}
+
+#[update(guard=check_members)]
+async fn proposal_to_bounty_done(args: BountyDone) -> Result<String, String> {
+    let bounty = with_state(|state| state.bounties.get(&args.associated_proposal_id))
+        .ok_or(String::from(crate::utils::BOUNTY_NOT_FOUND))?;
+
+    if bounty.bounty_status != BountyStatus::Claimed {
+        return Err(String::from("Only claimed bounties can be marked as done !"));
+    }
+    if args.tokens != bounty.tokens {
+        return Err(format!(
+            "Bounty done must release the escrowed {} tokens !",
+            bounty.tokens
+        ));
+    }
+
+    submit_proposal(ProposalInput {
+        proposal_title: format!("Bounty {} is done", args.associated_proposal_id),
+        proposal_description: args.description.clone(),
+        proposal_entry: args.proposal_entry.clone(),
+        proposal_type: ProposalType::BountyDone(args),
+        proposal_expired_at: None,
+    })
+    .await
+}
+
+#[update(guard=check_members)]
+async fn proposal_to_mint_token(args: MintTokenArgs) -> Result<String, String> {
+    if args.total_amount == 0 {
+        return Err(String::from("Tokens to mint must be greater than zero !"));
+    }
+
+    submit_proposal(ProposalInput {
+        proposal_title: format!("Mint {} tokens to the treasury", args.total_amount),
+        proposal_description: args.description.clone(),
+        proposal_entry: args.proposal_entry.clone(),
+        proposal_type: ProposalType::MintToken(args),
+        proposal_expired_at: None,
+    })
+    .await
+}
+
+#[update(guard=check_members)]
+async fn proposal_to_configure_sale(args: ConfigureSaleArgs) -> Result<String, String> {
+    with_state(|state| validate_sale_config(state, &args.config))?;
+
+    submit_proposal(ProposalInput {
+        proposal_title: String::from("Configure the token sale"),
+        proposal_description: args.description,
+        proposal_entry: args.proposal_entry,
+        proposal_type: ProposalType::ConfigureSale(args.config),
+        proposal_expired_at: None,
+    })
+    .await
+}
+
+#[update(guard=check_members)]
+async fn vote(proposal_id: String, voting: VoteParam) -> Result<ProposalState, String> {
+    let status = with_state(|state| {
+        vote_controller(state, &proposal_id, api::caller(), voting, api::time())
+    })?;
+
+    if status != ProposalState::Accepted {
+        if status != ProposalState::Open {
+            sync_proposal_with_backend(&proposal_id).await;
+        }
+        return Ok(status);
+    }
+
+    match run_accepted_proposal(proposal_id).await {
+        Ok(status) => Ok(status),
+        Err(err) => {
+            ic_cdk::println!("Failed to execute proposal: {}", err);
+            Ok(ProposalState::Unreachable)
+        }
+    }
+}
+
+#[update(guard=check_members)]
+async fn execute_proposal(proposal_id: String) -> Result<ProposalState, String> {
+    run_accepted_proposal(proposal_id).await
+}
+
+#[query(guard=prevent_anonymous)]
+fn get_proposal(proposal_id: String) -> Result<Proposals, String> {
+    with_state(|state| get_proposal_controller(state, &proposal_id, api::time()))
+        .ok_or(String::from(crate::utils::PROPOSAL_NOT_FOUND))
+}
+
+#[query(guard=prevent_anonymous)]
+fn list_proposals(page: Pagination) -> Vec<Proposals> {
+    with_state(|state| list_proposals_controller(state, page.start, page.end, api::time()))
+}
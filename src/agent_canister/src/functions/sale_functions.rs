@@ -0,0 +1,314 @@
+use crate::functions::{icrc1_fee, icrc1_transfer, icrc2_transfer_from, send_icrc1_transfer};
+use crate::proposal_route::{
+    claim_compensation, compensation_subaccount, finish_compensation, quote_buy, quote_sell,
+    record_compensation, record_trade, release_buy, release_sell, reserve_buy, reserve_sell,
+    sale_history, sale_reserve_subaccount, sale_supply_subaccount,
+};
+use crate::types::{
+    BuyTokensArgs, Pagination, PendingCompensation, SaleQuote, SaleState, SaleTrade,
+    SellTokensArgs, TradeSide,
+};
+use crate::{guards::*, with_state};
+use candid::Principal;
+use icrc_ledger_types::icrc1::account::Account;
+use ic_cdk::{api, query, update};
+
+fn sale_supply() -> Account {
+    Account {
+        owner: api::id(),
+        subaccount: Some(sale_supply_subaccount()),
+    }
+}
+
+// pays `amount` less the ledger fee back to `to`, the inner error means nothing was sent
+async fn pay_compensation(
+    side: &TradeSide,
+    ledger_canister_id: Principal,
+    to: Principal,
+    amount: u64,
+    created_at_time: u64,
+) -> Result<Result<(), String>, String> {
+    let fee = match icrc1_fee(ledger_canister_id).await {
+        Ok(fee) => fee,
+        Err(err) => return Ok(Err(err)),
+    };
+    if amount <= fee {
+        return Ok(Ok(()));
+    }
+
+    let sent = send_icrc1_transfer(
+        ledger_canister_id,
+        Some(compensation_subaccount(side)),
+        Account {
+            owner: to,
+            subaccount: None,
+        },
+        amount - fee,
+        None,
+        Some(created_at_time),
+    )
+    .await?;
+    Ok(sent.map(|_| ()))
+}
+
+// a compensation transfer gets a second attempt with the same created_at_time, so a first
+// attempt that reached the ledger without its reply is not paid twice; one that still fails
+// is kept for its trader to retry
+async fn compensate(
+    side: TradeSide,
+    ledger_canister_id: Principal,
+    to: Principal,
+    amount: u64,
+) -> Result<(), String> {
+    let created_at_time = api::time();
+
+    let mut result = Ok(Ok(()));
+    for _ in 0..crate::utils::COMPENSATION_ATTEMPTS {
+        result = pay_compensation(&side, ledger_canister_id, to, amount, created_at_time).await;
+        if let Ok(Ok(())) = result {
+            return Ok(());
+        }
+    }
+
+    let (error, created_at_time) = match result {
+        Ok(Ok(())) => return Ok(()),
+        Ok(Err(err)) => (err, None),
+        Err(err) => (err, Some(created_at_time)),
+    };
+    let compensation_id = with_state(|state| {
+        record_compensation(
+            state,
+            PendingCompensation {
+                compensation_id: 0,
+                trader: to,
+                side,
+                ledger_id: ledger_canister_id,
+                amount,
+                created_at_time,
+                error: error.clone(),
+                recorded_at: api::time(),
+                retrying: false,
+            },
+        )
+    });
+    Err(format!("{}, kept as compensation {}", error, compensation_id))
+}
+
+// the buyer approves `max_cost` plus the ledger fee on the NeuroPad ledger for this canister,
+// the ledger fee of the token payout is taken from the bought tokens
+#[update(guard=prevent_anonymous)]
+async fn buy_tokens(args: BuyTokensArgs) -> Result<SaleTrade, String> {
+    let buyer = api::caller();
+    let (config, quote) =
+        with_state(|state| reserve_buy(state, args.token_amount, args.max_cost))?;
+    let token_ledger_id = with_state(|state| state.agent().token_ledger_id);
+
+    let token_fee = match icrc1_fee(token_ledger_id).await {
+        Ok(fee) if quote.token_amount > fee => fee,
+        Ok(_) => {
+            with_state(|state| release_buy(state, quote.token_amount));
+            return Err(String::from("Buy amount does not cover the ledger fee !"));
+        }
+        Err(err) => {
+            with_state(|state| release_buy(state, quote.token_amount));
+            return Err(err);
+        }
+    };
+
+    let payment_block_index = match icrc2_transfer_from(
+        config.payment_ledger_id,
+        buyer,
+        Account {
+            owner: api::id(),
+            subaccount: Some(sale_reserve_subaccount()),
+        },
+        quote.payment_amount,
+        None,
+    )
+    .await
+    {
+        Ok(block_index) => block_index,
+        Err(err) => {
+            with_state(|state| release_buy(state, quote.token_amount));
+            return Err(err);
+        }
+    };
+
+    let token_block_index = match icrc1_transfer(
+        token_ledger_id,
+        Some(sale_supply_subaccount()),
+        Account {
+            owner: buyer,
+            subaccount: None,
+        },
+        quote.token_amount - token_fee,
+        None,
+        None,
+    )
+    .await
+    {
+        Ok(block_index) => block_index,
+        Err(err) => {
+            with_state(|state| release_buy(state, quote.token_amount));
+            // the payment reached the reserve, hand it back to the buyer
+            if let Err(refund_err) =
+                compensate(TradeSide::Buy, config.payment_ledger_id, buyer, quote.payment_amount)
+                    .await
+            {
+                return Err(format!("{}, refund failed: {}", err, refund_err));
+            }
+            return Err(err);
+        }
+    };
+
+    Ok(with_state(|state| {
+        record_trade(
+            state,
+            buyer,
+            TradeSide::Buy,
+            &quote,
+            token_block_index,
+            payment_block_index,
+            api::time(),
+        )
+    }))
+}
+
+// the seller approves `token_amount` plus the ledger fee on the agent token ledger,
+// the ledger fee of the payout is taken from the returned amount
+#[update(guard=prevent_anonymous)]
+async fn sell_tokens(args: SellTokensArgs) -> Result<SaleTrade, String> {
+    let seller = api::caller();
+    let (config, quote) =
+        with_state(|state| reserve_sell(state, args.token_amount, args.min_return))?;
+    let token_ledger_id = with_state(|state| state.agent().token_ledger_id);
+
+    let payment_fee = match icrc1_fee(config.payment_ledger_id).await {
+        Ok(fee) if quote.payment_amount > fee => fee,
+        Ok(_) => {
+            with_state(|state| release_sell(state, &quote));
+            return Err(String::from("Sell amount does not cover the ledger fee !"));
+        }
+        Err(err) => {
+            with_state(|state| release_sell(state, &quote));
+            return Err(err);
+        }
+    };
+
+    let token_block_index =
+        match icrc2_transfer_from(token_ledger_id, seller, sale_supply(), quote.token_amount, None)
+            .await
+        {
+            Ok(block_index) => block_index,
+            Err(err) => {
+                with_state(|state| release_sell(state, &quote));
+                return Err(err);
+            }
+        };
+
+    let payment_block_index = match icrc1_transfer(
+        config.payment_ledger_id,
+        Some(sale_reserve_subaccount()),
+        Account {
+            owner: seller,
+            subaccount: None,
+        },
+        quote.payment_amount - payment_fee,
+        None,
+        None,
+    )
+    .await
+    {
+        Ok(block_index) => block_index,
+        Err(err) => {
+            // the tokens already reached the sale supply, hand them back to the seller
+            with_state(|state| release_sell(state, &quote));
+            if let Err(return_err) =
+                compensate(TradeSide::Sell, token_ledger_id, seller, quote.token_amount).await
+            {
+                return Err(format!("{}, returning the tokens failed: {}", err, return_err));
+            }
+            return Err(err);
+        }
+    };
+
+    Ok(with_state(|state| {
+        record_trade(
+            state,
+            seller,
+            TradeSide::Sell,
+            &quote,
+            token_block_index,
+            payment_block_index,
+            api::time(),
+        )
+    }))
+}
+
+// pays out a compensation of the caller that failed before
+#[update(guard=prevent_anonymous)]
+async fn retry_compensation(compensation_id: u64) -> Result<(), String> {
+    let compensation =
+        with_state(|state| claim_compensation(state, compensation_id, api::caller()))?;
+    let created_at_time = compensation.created_at_time.unwrap_or(api::time());
+
+    let result = pay_compensation(
+        &compensation.side,
+        compensation.ledger_id,
+        compensation.trader,
+        compensation.amount,
+        created_at_time,
+    )
+    .await;
+    with_state(|state| finish_compensation(state, compensation_id, created_at_time, &result));
+
+    result.and_then(|paid| paid)
+}
+
+#[query(guard=prevent_anonymous)]
+fn quote_buy_tokens(token_amount: u64) -> Result<SaleQuote, String> {
+    with_state(|state| quote_buy(state, token_amount))
+}
+
+#[query(guard=prevent_anonymous)]
+fn quote_sell_tokens(token_amount: u64) -> Result<SaleQuote, String> {
+    with_state(|state| quote_sell(state, token_amount))
+}
+
+#[query(guard=prevent_anonymous)]
+fn get_sale_state() -> SaleState {
+    with_state(|state| state.sale.get().clone())
+}
+
+#[query(guard=prevent_anonymous)]
+fn get_sale_history(page: Pagination) -> Vec<SaleTrade> {
+    with_state(|state| sale_history(state, page.start, page.end))
+}
+
+#[query(guard=prevent_anonymous)]
+fn get_my_trades() -> Vec<SaleTrade> {
+    let caller = api::caller();
+
+    with_state(|state| {
+        state
+            .sale_trades
+            .iter()
+            .map(|(_, trade)| trade)
+            .filter(|trade| trade.trader == caller)
+            .collect()
+    })
+}
+
+#[query(guard=prevent_anonymous)]
+fn get_my_compensations() -> Vec<PendingCompensation> {
+    let caller = api::caller();
+
+    with_state(|state| {
+        state
+            .compensations
+            .iter()
+            .map(|(_, compensation)| compensation)
+            .filter(|compensation| compensation.trader == caller)
+            .collect()
+    })
+}
@@ -0,0 +1,103 @@
+use crate::functions::{icrc1_fee, icrc1_transfer, icrc2_transfer_from};
+use crate::proposal_route::{
+    record_stake, release_unstake, reserve_unstake, stake_status, staking_subaccount,
+    validate_lock,
+};
+use crate::types::{StakeArgs, StakeStatus};
+use crate::{guards::*, with_state};
+use candid::{Nat, Principal};
+use icrc_ledger_types::icrc1::account::Account;
+use ic_cdk::{api, query, update};
+
+// the staker approves `amount` plus the ledger fee on the agent token ledger for this canister,
+// a zero amount only extends the lock
+#[update(guard=prevent_anonymous)]
+async fn stake(args: StakeArgs) -> Result<StakeStatus, String> {
+    let staker = api::caller();
+    let ledger_canister_id = with_state(|state| state.agent().token_ledger_id);
+    if ledger_canister_id == Principal::anonymous() {
+        return Err(String::from(crate::utils::LEDGER_NOT_SET));
+    }
+
+    let lock_duration =
+        with_state(|state| validate_lock(state, staker, args.lock_seconds, api::time()))?;
+    if args.amount == 0 && lock_duration.is_none() {
+        return Err(String::from("Stake amount must be greater than zero !"));
+    }
+
+    if args.amount > 0 {
+        icrc2_transfer_from(
+            ledger_canister_id,
+            staker,
+            Account {
+                owner: api::id(),
+                subaccount: Some(staking_subaccount(&staker)),
+            },
+            args.amount,
+            None,
+        )
+        .await?;
+    }
+
+    let now = api::time();
+    Ok(with_state(|state| {
+        stake_status(record_stake(state, staker, args.amount, lock_duration, now), now)
+    }))
+}
+
+// sends unlocked stake back to the caller, the ledger fee is taken from `amount`
+#[update(guard=prevent_anonymous)]
+async fn unstake(amount: u64) -> Result<Nat, String> {
+    let staker = api::caller();
+    let ledger_canister_id = with_state(|state| state.agent().token_ledger_id);
+    if ledger_canister_id == Principal::anonymous() {
+        return Err(String::from(crate::utils::LEDGER_NOT_SET));
+    }
+
+    with_state(|state| reserve_unstake(state, staker, amount, api::time()))?;
+
+    let result = match icrc1_fee(ledger_canister_id).await {
+        Ok(fee) if amount > fee => {
+            icrc1_transfer(
+                ledger_canister_id,
+                Some(staking_subaccount(&staker)),
+                Account {
+                    owner: staker,
+                    subaccount: None,
+                },
+                amount - fee,
+                None,
+                None,
+            )
+            .await
+        }
+        Ok(_) => Err(String::from("Unstake amount does not cover the ledger fee !")),
+        Err(err) => Err(err),
+    };
+
+    if result.is_err() {
+        with_state(|state| release_unstake(state, staker, amount, api::time()));
+    }
+
+    result
+}
+
+#[query(guard=prevent_anonymous)]
+fn get_stake(owner: Principal) -> Result<StakeStatus, String> {
+    with_state(|state| state.stakes.get(&owner))
+        .map(|balance| stake_status(balance, api::time()))
+        .ok_or(String::from(crate::utils::STAKE_NOT_FOUND))
+}
+
+#[query(guard=prevent_anonymous)]
+fn list_stakes() -> Vec<StakeStatus> {
+    let now = api::time();
+
+    with_state(|state| {
+        state
+            .stakes
+            .iter()
+            .map(|(_, balance)| stake_status(balance, now))
+            .collect()
+    })
+}
@@ -0,0 +1,64 @@
+use crate::functions::{icrc1_fee, icrc1_transfer};
+use crate::proposal_route::{release_claim, reserve_claim, vesting_status, vesting_subaccount};
+use crate::types::VestingStatus;
+use crate::{guards::*, with_state};
+use candid::{Nat, Principal};
+use icrc_ledger_types::icrc1::account::Account;
+use ic_cdk::{api, query, update};
+
+// pays out the vested tokens of the caller, the ledger fee is taken from them
+#[update(guard=prevent_anonymous)]
+async fn claim_vested() -> Result<Nat, String> {
+    let beneficiary = api::caller();
+    let ledger_canister_id = with_state(|state| state.agent().token_ledger_id);
+    if ledger_canister_id == Principal::anonymous() {
+        return Err(String::from(crate::utils::LEDGER_NOT_SET));
+    }
+
+    let claimable = with_state(|state| reserve_claim(state, beneficiary, api::time()))?;
+
+    let result = match icrc1_fee(ledger_canister_id).await {
+        Ok(fee) if claimable > fee => {
+            icrc1_transfer(
+                ledger_canister_id,
+                Some(vesting_subaccount(&beneficiary)),
+                Account {
+                    owner: beneficiary,
+                    subaccount: None,
+                },
+                claimable - fee,
+                None,
+                None,
+            )
+            .await
+        }
+        Ok(_) => Err(String::from("Vested amount does not cover the ledger fee yet !")),
+        Err(err) => Err(err),
+    };
+
+    if result.is_err() {
+        with_state(|state| release_claim(state, beneficiary, claimable));
+    }
+
+    result
+}
+
+#[query(guard=prevent_anonymous)]
+fn get_vesting_status(beneficiary: Principal) -> Result<VestingStatus, String> {
+    with_state(|state| state.vesting_schedules.get(&beneficiary))
+        .map(|schedule| vesting_status(schedule, api::time()))
+        .ok_or(String::from(crate::utils::VESTING_NOT_FOUND))
+}
+
+#[query(guard=prevent_anonymous)]
+fn list_vesting_schedules() -> Vec<VestingStatus> {
+    let now = api::time();
+
+    with_state(|state| {
+        state
+            .vesting_schedules
+            .iter()
+            .map(|(_, schedule)| vesting_status(schedule, now))
+            .collect()
+    })
+}
@@ -1,9 +1,30 @@
 use candid::Principal;
 use ic_cdk::api;
 
+use crate::with_state;
+
 pub fn prevent_anonymous() -> Result<(), String> {
     if api::caller() == Principal::anonymous() {
         return Err(String::from(crate::utils::WARNING_ANONYMOUS_CALL));
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+pub fn is_controller() -> Result<(), String> {
+    if api::is_controller(&api::caller()) {
+        Ok(())
+    } else {
+        Err(String::from(crate::utils::NOT_A_CONTROLLER))
+    }
+}
+
+// only members of the agent can create and vote on proposals
+pub fn check_members() -> Result<(), String> {
+    prevent_anonymous()?;
+    let caller = api::caller();
+    if with_state(|state| state.agent().members.contains(&caller)) {
+        Ok(())
+    } else {
+        Err(String::from(crate::utils::NOT_A_MEMBER))
+    }
+}
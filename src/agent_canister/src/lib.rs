@@ -1,5 +1,5 @@
 mod types;
-use ic_cdk::{export_candid, init};
+use ic_cdk::{export_candid, init, post_upgrade, pre_upgrade};
 use std::cell::RefCell;
 pub mod proposal_route;
 mod state_handler;
@@ -8,10 +8,11 @@ mod memory;
 mod functions;
 mod guards;
 extern crate ic_cdk_macros;
-use candid::Principal;
+use candid::{Nat, Principal};
 use types::*;
 mod utils;
-use icrc_ledger_types::icrc1::transfer::BlockIndex;
+#[cfg(test)]
+mod test_fixtures;
 
 thread_local! {
     static STATE: RefCell<State> = RefCell::new(State::new());
@@ -23,13 +24,34 @@ pub fn with_state<R>(f: impl FnOnce(&mut State) -> R) -> R {
 
 #[init]
 async fn init(agent_input: AgentCreationInput) {    
+    let proposal_entry = match agent_input.proposal_entry {
+        Some(places) => places,
+        None => vec![ProposalPlace {
+            place_name: String::from(utils::DEFAULT_PROPOSAL_PLACE),
+            min_required_thredshold: 0,
+            quorum_percentage: utils::DEFAULT_QUORUM_PERCENTAGE,
+            approval_percentage: utils::DEFAULT_APPROVAL_PERCENTAGE,
+        }],
+    };
+
+    if let Err(err) = proposal_route::validate_proposal_places(&proposal_entry) {
+        ic_cdk::trap(&err);
+    }
+
+    let mut council_members: Vec<Principal> = Vec::new();
+    for member in agent_input.members.iter() {
+        if !council_members.contains(member) {
+            council_members.push(*member);
+        }
+    }
+
     let new_agent = AgentDetails {
         agent_id: ic_cdk::api::id(),
         agent_name: agent_input.agent_name,
         image_canister: agent_input.image_canister,
-        members: agent_input.members.clone(),
+        members: council_members.clone(),
         image_id: agent_input.image_id,
-        members_count: agent_input.members.len() as u32,
+        members_count: council_members.len() as u32,
         agent_category : agent_input.agent_category,
         agent_type : agent_input.agent_type,
         agent_overview : agent_input.agent_overview,
@@ -43,11 +65,48 @@ async fn init(agent_input: AgentCreationInput) {
         agent_lunch_time : agent_input.agent_lunch_time,
         token_symbol: agent_input.token_symbol,
         token_ledger_id: Principal::anonymous(),
+        proposal_entry,
+        member_groups: vec![MemberGroup {
+            group_name: String::from(utils::DEFAULT_MEMBER_GROUP),
+            group_members: council_members,
+        }],
+        // the canister installing this agent, NeuroPad_backend for launched agents
+        neuropad_backend_id: ic_cdk::caller(),
     };
 
     with_state(|state| {
-        state.agent = new_agent.clone();
+        state
+            .agent
+            .set(new_agent.clone())
+            .expect("failed to save agent details");
+        state
+            .schema_version
+            .set(state_handler::STATE_SCHEMA_VERSION)
+            .expect("failed to save schema version");
+        // vesting starts with the launch of the agent
+        proposal_route::create_vesting_schedules(
+            state,
+            agent_input.vesting.unwrap_or_default(),
+            ic_cdk::api::time(),
+        );
+    });
+}
+
+#[pre_upgrade]
+fn pre_upgrade() {
+    // everything already lives in stable memory, only record the layout it was written in
+    with_state(|state| {
+        state
+            .schema_version
+            .set(state_handler::STATE_SCHEMA_VERSION)
+            .expect("failed to save schema version");
     });
 }
 
+#[post_upgrade]
+fn post_upgrade() {
+    with_state(state_handler::migrate_state);
+    proposal_route::rearm_proposal_checkers();
+}
+
 export_candid!();
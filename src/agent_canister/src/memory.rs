@@ -1,8 +1,76 @@
-use ic_stable_structures::memory_manager::MemoryManager;
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
 use ic_stable_structures::DefaultMemoryImpl;
 use std::cell::RefCell;
 
+const AGENT_DATA: MemoryId = MemoryId::new(0);
+const PROPOSAL_DATA: MemoryId = MemoryId::new(1);
+const PROPOSAL_COUNT: MemoryId = MemoryId::new(2);
+const SCHEMA_VERSION: MemoryId = MemoryId::new(3);
+const BOUNTY_DATA: MemoryId = MemoryId::new(4);
+const POLL_DATA: MemoryId = MemoryId::new(5);
+const COMMENT_DATA: MemoryId = MemoryId::new(6);
+const COMMENT_COUNT: MemoryId = MemoryId::new(7);
+const SALE_STATE: MemoryId = MemoryId::new(8);
+const SALE_TRADE_DATA: MemoryId = MemoryId::new(9);
+const VESTING_DATA: MemoryId = MemoryId::new(10);
+const STAKE_DATA: MemoryId = MemoryId::new(11);
+const COMPENSATION_DATA: MemoryId = MemoryId::new(12);
+
+pub type Memory = VirtualMemory<DefaultMemoryImpl>;
+
 thread_local! {
     static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> =
         RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
-}
\ No newline at end of file
+}
+
+pub fn get_agent_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(AGENT_DATA))
+}
+
+pub fn get_proposal_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(PROPOSAL_DATA))
+}
+
+pub fn get_proposal_count_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(PROPOSAL_COUNT))
+}
+
+pub fn get_schema_version_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(SCHEMA_VERSION))
+}
+
+pub fn get_bounty_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(BOUNTY_DATA))
+}
+
+pub fn get_poll_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(POLL_DATA))
+}
+
+pub fn get_comment_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(COMMENT_DATA))
+}
+
+pub fn get_comment_count_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(COMMENT_COUNT))
+}
+
+pub fn get_sale_state_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(SALE_STATE))
+}
+
+pub fn get_sale_trade_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(SALE_TRADE_DATA))
+}
+
+pub fn get_vesting_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(VESTING_DATA))
+}
+
+pub fn get_stake_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(STAKE_DATA))
+}
+
+pub fn get_compensation_memory() -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(COMPENSATION_DATA))
+}
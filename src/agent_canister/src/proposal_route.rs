@@ -1,2 +1,29 @@
 mod auto_run_proposal;
-pub use auto_run_proposal::*;
\ No newline at end of file
+pub use auto_run_proposal::*;
+
+mod proposal_controller;
+pub use proposal_controller::*;
+
+mod execute_proposal;
+pub use execute_proposal::*;
+
+mod member_controller;
+pub use member_controller::*;
+
+mod bounty_controller;
+pub use bounty_controller::*;
+
+mod poll_controller;
+pub use poll_controller::*;
+
+mod comment_controller;
+pub use comment_controller::*;
+
+mod sale_controller;
+pub use sale_controller::*;
+
+mod vesting_controller;
+pub use vesting_controller::*;
+
+mod staking_controller;
+pub use staking_controller::*;
@@ -1,11 +1,87 @@
+use crate::proposal_route::{refresh_proposal_state, run_accepted_proposal, sync_proposal_with_backend};
+use crate::types::ProposalState;
+use crate::with_state;
 use ic_cdk_timers::set_timer;
 use std::time::Duration;
 
-// use crate::functions::icrc_transfer;
+// wakes up at the expiry of a proposal, `expired_at` is an absolute time in nanoseconds
+pub fn start_proposal_checker(proposal_id: String, expired_at: u64) {
+    let delay = expired_at.saturating_sub(ic_cdk::api::time());
 
-pub fn start_proposal_checker(expird_at : u64) {
-    set_timer(Duration::from_nanos(expird_at), move || {
-        // check_proposals();
-        // icrc_transfer(ledger_canister_id, args)
+    set_timer(Duration::from_nanos(delay), move || {
+        ic_cdk::spawn(check_proposal(proposal_id));
     });
-}
\ No newline at end of file
+}
+
+// settles the final tally of the proposal and runs it when it got accepted
+async fn check_proposal(proposal_id: String) {
+    let Some((previous, status)) = with_state(|state| {
+        let mut proposal = state.proposals.get(&proposal_id)?;
+        let previous = proposal.proposal_status.clone();
+        refresh_proposal_state(&mut proposal, ic_cdk::api::time());
+
+        let status = proposal.proposal_status.clone();
+        state.proposals.insert(proposal_id.clone(), proposal);
+        Some((previous, status))
+    }) else {
+        return;
+    };
+
+    if status != ProposalState::Accepted {
+        if status != previous {
+            sync_proposal_with_backend(&proposal_id).await;
+        }
+        return;
+    }
+
+    if let Err(err) = run_accepted_proposal(proposal_id.clone()).await {
+        ic_cdk::println!("Failed to execute proposal {}: {}", proposal_id, err);
+    }
+}
+
+// timers do not survive an upgrade, so they are armed again from the stored proposals
+pub fn rearm_proposal_checkers() {
+    // an execution cut off by the upgrade may or may not have reached the ledger, it is left
+    // Unreachable so members run it again, which its fixed created_at_time keeps deduplicated
+    let interrupted: Vec<String> = with_state(|state| {
+        let interrupted: Vec<String> = state
+            .proposals
+            .iter()
+            .filter(|(_, proposal)| proposal.proposal_status == ProposalState::Executing)
+            .map(|(proposal_id, _)| proposal_id)
+            .collect();
+
+        for proposal_id in interrupted.iter() {
+            if let Some(mut proposal) = state.proposals.get(proposal_id) {
+                proposal.proposal_status = ProposalState::Unreachable;
+                proposal.execution_error = Some(String::from(crate::utils::EXECUTION_INTERRUPTED));
+                state.proposals.insert(proposal_id.clone(), proposal);
+            }
+        }
+
+        interrupted
+    });
+
+    for proposal_id in interrupted {
+        set_timer(Duration::ZERO, move || {
+            ic_cdk::spawn(async move { sync_proposal_with_backend(&proposal_id).await });
+        });
+    }
+
+    let pending: Vec<(String, u64)> = with_state(|state| {
+        state
+            .proposals
+            .iter()
+            .filter_map(|(proposal_id, proposal)| match proposal.proposal_status {
+                ProposalState::Open => Some((proposal_id, proposal.proposal_expired_at)),
+                // accepted before the upgrade but never executed, run it right away
+                ProposalState::Accepted => Some((proposal_id, 0)),
+                _ => None,
+            })
+            .collect()
+    });
+
+    for (proposal_id, expired_at) in pending {
+        start_proposal_checker(proposal_id, expired_at);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::state_handler::State;
+use crate::types::{Bounty, BountyStatus};
+use candid::Principal;
+use icrc_ledger_types::icrc1::account::Subaccount;
+use sha2::{Digest, Sha256};
+
+// every bounty keeps its escrow in its own subaccount of the agent canister
+pub fn bounty_escrow_subaccount(bounty_id: &str) -> Subaccount {
+    let mut hasher = Sha256::new();
+    hasher.update(b"bounty-escrow:");
+    hasher.update(bounty_id.as_bytes());
+
+    let mut subaccount = [0u8; 32];
+    subaccount.copy_from_slice(&hasher.finalize());
+    subaccount
+}
+
+pub fn claim_bounty_controller(
+    state: &mut State,
+    bounty_id: &str,
+    contributor: Principal,
+    now: u64,
+) -> Result<(), String> {
+    let mut bounty = state
+        .bounties
+        .get(&bounty_id.to_string())
+        .ok_or(String::from(crate::utils::BOUNTY_NOT_FOUND))?;
+
+    if bounty.bounty_status != BountyStatus::Open {
+        return Err(String::from("Bounty is not open for claims !"));
+    }
+    if now >= bounty.bounty_expired_at {
+        return Err(String::from("Bounty has expired !"));
+    }
+
+    bounty.bounty_status = BountyStatus::Claimed;
+    bounty.claimed_by = Some(contributor);
+    bounty.claimed_at = Some(now);
+    state.bounties.insert(bounty_id.to_string(), bounty);
+
+    Ok(())
+}
+
+// moves a bounty to its settled status before the ledger call, returns the previous bounty
+// so that the caller can restore it if the transfer fails
+pub fn settle_bounty_controller(
+    state: &mut State,
+    bounty_id: &str,
+    settled_status: BountyStatus,
+    now: u64,
+) -> Result<Bounty, String> {
+    let bounty = state
+        .bounties
+        .get(&bounty_id.to_string())
+        .ok_or(String::from(crate::utils::BOUNTY_NOT_FOUND))?;
+
+    match settled_status {
+        BountyStatus::Completed => {
+            if bounty.bounty_status != BountyStatus::Claimed {
+                return Err(String::from("Only claimed bounties can be completed !"));
+            }
+            if bounty.claimed_by.is_none() {
+                return Err(String::from("Claimed bounty has no claimant !"));
+            }
+        }
+        BountyStatus::Refunded => {
+            if bounty.bounty_status != BountyStatus::Open
+                && bounty.bounty_status != BountyStatus::Claimed
+            {
+                return Err(String::from("Bounty is already settled !"));
+            }
+            if now < bounty.bounty_expired_at {
+                return Err(String::from("Bounty can only be refunded after it expires !"));
+            }
+        }
+        _ => return Err(String::from("Invalid bounty settlement !")),
+    }
+
+    let mut settled = bounty.clone();
+    settled.bounty_status = settled_status;
+    state.bounties.insert(bounty_id.to_string(), settled);
+
+    Ok(bounty)
+}
+
+pub fn list_bounties_controller(state: &State, start: u32, end: u32) -> Vec<Bounty> {
+    let mut bounties: Vec<Bounty> = state.bounties.iter().map(|(_, bounty)| bounty).collect();
+    bounties.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+    bounties
+        .into_iter()
+        .skip(start as usize)
+        .take(end.saturating_sub(start) as usize)
+        .collect()
+}
@@ -0,0 +1,119 @@
+use crate::state_handler::State;
+use crate::types::{Comment, CommentLikeArgs, ReplayComment, ReplyCommentArgs};
+use candid::Principal;
+
+fn validate_comment_text(text: &str) -> Result<(), String> {
+    if text.trim().is_empty() {
+        return Err(String::from("Comment cannot be empty !"));
+    }
+    if text.chars().count() > crate::utils::MAX_COMMENT_LENGTH {
+        return Err(format!(
+            "Comment cannot be longer than {} characters !",
+            crate::utils::MAX_COMMENT_LENGTH
+        ));
+    }
+
+    Ok(())
+}
+
+// looks up a comment and makes sure it belongs to the given proposal
+fn proposal_comment(state: &State, proposal_id: &str, comment_id: &str) -> Result<Comment, String> {
+    state
+        .comments
+        .get(&comment_id.to_string())
+        .filter(|comment| comment.proposal_id == proposal_id)
+        .ok_or(String::from(crate::utils::COMMENT_NOT_FOUND))
+}
+
+pub fn comment_on_proposal_controller(
+    state: &mut State,
+    proposal_id: String,
+    comment_text: String,
+    author: Principal,
+    now: u64,
+) -> Result<String, String> {
+    if !state.proposals.contains_key(&proposal_id) {
+        return Err(String::from(crate::utils::PROPOSAL_NOT_FOUND));
+    }
+    validate_comment_text(&comment_text)?;
+
+    let comment_id = state.next_comment_id();
+
+    let comment = Comment {
+        author_principal: author,
+        comment_text,
+        comment_id: comment_id.clone(),
+        proposal_id,
+        replies: Vec::new(),
+        likes: 0,
+        liked_by: Vec::new(),
+        created_at: now,
+    };
+
+    state.comments.insert(comment_id.clone(), comment);
+
+    Ok(comment_id)
+}
+
+pub fn reply_to_comment_controller(
+    state: &mut State,
+    args: ReplyCommentArgs,
+    author: Principal,
+    now: u64,
+) -> Result<(), String> {
+    validate_comment_text(&args.comment)?;
+
+    let mut comment = proposal_comment(state, &args.proposal_id, &args.comment_id)?;
+
+    comment.replies.push(ReplayComment {
+        reply_comment: args.comment,
+        commented_by: author,
+        created_at: now,
+    });
+    state.comments.insert(args.comment_id, comment);
+
+    Ok(())
+}
+
+// liking twice keeps a single like, returns the like count of the comment
+pub fn like_comment_controller(
+    state: &mut State,
+    args: CommentLikeArgs,
+    liked_by: Principal,
+) -> Result<u16, String> {
+    let mut comment = proposal_comment(state, &args.proposal_id, &args.comment_id)?;
+
+    if comment.liked_by.contains(&liked_by) {
+        return Ok(comment.likes);
+    }
+
+    comment.liked_by.push(liked_by);
+    comment.likes = comment.likes.saturating_add(1);
+
+    let likes = comment.likes;
+    state.comments.insert(args.comment_id, comment);
+
+    Ok(likes)
+}
+
+// comments of a proposal, oldest first
+pub fn get_comments_controller(
+    state: &State,
+    proposal_id: &str,
+    start: u32,
+    end: u32,
+) -> Vec<Comment> {
+    let mut comments: Vec<Comment> = state
+        .comments
+        .iter()
+        .map(|(_, comment)| comment)
+        .filter(|comment| comment.proposal_id == proposal_id)
+        .collect();
+    comments.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+    comments
+        .into_iter()
+        .skip(start as usize)
+        .take(end.saturating_sub(start) as usize)
+        .collect()
+}
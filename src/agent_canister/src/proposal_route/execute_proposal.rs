@@ -0,0 +1,383 @@
+use crate::functions::{call_inter_canister, icrc1_fee, icrc1_transfer, send_icrc1_transfer};
+use crate::proposal_route::{
+    add_member_to_group, bounty_escrow_subaccount, configure_sale_controller,
+    refresh_proposal_state, release_sale_supply, remove_member_from_group, reserve_sale_supply,
+    sale_supply_subaccount, settle_bounty_controller,
+};
+use crate::types::{
+    Bounty, BountyRaised, BountyStatus, MintRequest, MintTokenArgs, ProposalState, ProposalType,
+    ProposalValueStore, Proposals, SaleConfig, TokenTransferPolicy,
+};
+use crate::with_state;
+use candid::{Nat, Principal};
+use icrc_ledger_types::icrc1::account::Account;
+
+// runs the action of an accepted proposal and records the outcome on it,
+// proposals whose action failed are left Unreachable and can be run again
+pub async fn run_accepted_proposal(proposal_id: String) -> Result<ProposalState, String> {
+    let proposal = with_state(|state| {
+        let mut proposal = state
+            .proposals
+            .get(&proposal_id)
+            .ok_or(String::from(crate::utils::PROPOSAL_NOT_FOUND))?;
+
+        let now = ic_cdk::api::time();
+
+        // a proposal can also be accepted by the final tally at its expiry
+        refresh_proposal_state(&mut proposal, now);
+
+        if proposal.proposal_status != ProposalState::Accepted
+            && proposal.proposal_status != ProposalState::Unreachable
+        {
+            state.proposals.insert(proposal_id.clone(), proposal);
+            return Err(String::from(crate::utils::PROPOSAL_NOT_ACCEPTED));
+        }
+
+        proposal.proposal_status = ProposalState::Executing;
+        proposal.execution_created_at.get_or_insert(now);
+        state.proposals.insert(proposal_id.clone(), proposal.clone());
+        Ok(proposal)
+    })?;
+
+    let result = execute_proposal_action(&proposal).await;
+
+    let status = match result {
+        Ok(_) => ProposalState::Succeeded,
+        Err(_) => ProposalState::Unreachable,
+    };
+
+    with_state(|state| {
+        if let Some(mut proposal) = state.proposals.get(&proposal_id) {
+            proposal.proposal_status = status.clone();
+            match &result {
+                Ok(block_index) => {
+                    proposal.executed_block_index = block_index.clone();
+                    proposal.execution_error = None;
+                }
+                Err(err) => proposal.execution_error = Some(err.clone()),
+            }
+            state.proposals.insert(proposal_id.clone(), proposal);
+        }
+    });
+
+    sync_proposal_with_backend(&proposal_id).await;
+
+    result.map(|_| status)
+}
+
+// returns the ledger block index for actions that move tokens
+async fn execute_proposal_action(proposal: &Proposals) -> Result<Option<Nat>, String> {
+    match &proposal.proposal_type {
+        ProposalType::GeneralPurpose => Ok(None),
+        ProposalType::TokenTransfer(args) => {
+            transfer_treasury_tokens(&proposal.proposal_id, args, proposal.execution_created_at)
+                .await
+                .map(Some)
+        }
+        ProposalType::AddMember(args) => {
+            with_state(|state| {
+                state.update_agent(|agent| {
+                    add_member_to_group(agent, &args.group_name, args.new_member)
+                })
+            });
+            sync_members_with_backend().await.map(|_| None)
+        }
+        ProposalType::RemoveMember(args) => {
+            with_state(|state| {
+                state.update_agent(|agent| {
+                    remove_member_from_group(agent, &args.group_name, args.action_member)
+                })
+            })?;
+            sync_members_with_backend().await.map(|_| None)
+        }
+        ProposalType::BountyRaised(args) => {
+            escrow_bounty(&proposal.proposal_id, args, proposal.execution_created_at)
+                .await
+                .map(Some)
+        }
+        ProposalType::BountyDone(args) => {
+            settle_bounty(&args.associated_proposal_id, BountyStatus::Completed)
+                .await
+                .map(Some)
+        }
+        ProposalType::MintToken(args) => {
+            mint_treasury_tokens(&proposal.proposal_id, args, proposal.execution_created_at)
+                .await
+                .map(Some)
+        }
+        ProposalType::ConfigureSale(config) => {
+            configure_sale(&proposal.proposal_id, config, proposal.execution_created_at).await
+        }
+    }
+}
+
+// the backend owns the minting account of the agent ledger and mints into the treasury
+async fn mint_treasury_tokens(
+    proposal_id: &str,
+    args: &MintTokenArgs,
+    created_at_time: Option<u64>,
+) -> Result<Nat, String> {
+    let neuropad_backend_id = with_state(|state| state.agent().neuropad_backend_id);
+
+    call_inter_canister::<MintRequest, Result<Nat, String>>(
+        "mint_agent_tokens",
+        MintRequest {
+            proposal_id: proposal_id.to_string(),
+            amount: Nat::from(args.total_amount),
+            created_at_time,
+        },
+        neuropad_backend_id,
+    )
+    .await?
+}
+
+// funds the bounty subaccount from the treasury, the bounty id is the raising proposal id
+async fn escrow_bounty(
+    proposal_id: &str,
+    args: &BountyRaised,
+    created_at_time: Option<u64>,
+) -> Result<Nat, String> {
+    if let Some(bounty) = with_state(|state| state.bounties.get(&proposal_id.to_string())) {
+        // already escrowed by an earlier execution
+        return Ok(bounty.escrow_block_index);
+    }
+
+    let ledger_canister_id = with_state(|state| state.agent().token_ledger_id);
+    let escrow_subaccount = bounty_escrow_subaccount(proposal_id);
+
+    // the escrow also covers the fee of paying the bounty out again
+    let fee = icrc1_fee(ledger_canister_id).await?;
+
+    let escrow_block_index = icrc1_transfer(
+        ledger_canister_id,
+        None,
+        Account {
+            owner: ic_cdk::api::id(),
+            subaccount: Some(escrow_subaccount),
+        },
+        args.tokens.saturating_add(fee),
+        Some(proposal_id.as_bytes().to_vec()),
+        created_at_time,
+    )
+    .await?;
+
+    let now = ic_cdk::api::time();
+    let bounty = Bounty {
+        bounty_id: proposal_id.to_string(),
+        bounty_task: args.bounty_task.clone(),
+        description: args.description.clone(),
+        tokens: args.tokens,
+        escrow_subaccount: escrow_subaccount.to_vec(),
+        escrow_block_index: escrow_block_index.clone(),
+        bounty_status: BountyStatus::Open,
+        claimed_by: None,
+        claimed_at: None,
+        created_at: now,
+        bounty_expired_at: now.saturating_add(crate::utils::BOUNTY_DURATION),
+        settled_block_index: None,
+    };
+
+    with_state(|state| state.bounties.insert(proposal_id.to_string(), bounty));
+
+    Ok(escrow_block_index)
+}
+
+// moves agent tokens between the treasury and the sale supply subaccount so that the
+// subaccount covers the new supply, then applies the config
+async fn configure_sale(
+    proposal_id: &str,
+    config: &SaleConfig,
+    created_at_time: Option<u64>,
+) -> Result<Option<Nat>, String> {
+    let delta = with_state(|state| reserve_sale_supply(state, config))?;
+
+    match move_sale_supply(proposal_id, delta, created_at_time).await {
+        Ok(Ok(block_index)) => {
+            with_state(|state| configure_sale_controller(state, config.clone()))?;
+            Ok(block_index)
+        }
+        Ok(Err(err)) => {
+            with_state(|state| release_sale_supply(state, delta));
+            Err(err)
+        }
+        // the tokens may have moved, so the supply stays reserved
+        Err(err) => Err(err),
+    }
+}
+
+// the inner error means no tokens moved, see `send_icrc1_transfer`
+async fn move_sale_supply(
+    proposal_id: &str,
+    delta: i128,
+    created_at_time: Option<u64>,
+) -> Result<Result<Option<Nat>, String>, String> {
+    let ledger_canister_id = with_state(|state| state.agent().token_ledger_id);
+    let treasury = Account {
+        owner: ic_cdk::api::id(),
+        subaccount: None,
+    };
+    let supply = Account {
+        owner: ic_cdk::api::id(),
+        subaccount: Some(sale_supply_subaccount()),
+    };
+    let memo = Some(proposal_id.as_bytes().to_vec());
+    let too_large = || String::from("Sale supply is too large !");
+
+    if delta > 0 {
+        let Ok(amount) = u64::try_from(delta) else {
+            return Ok(Err(too_large()));
+        };
+        let sent =
+            send_icrc1_transfer(ledger_canister_id, None, supply, amount, memo, created_at_time)
+                .await?;
+        Ok(sent.map(Some))
+    } else if delta < 0 {
+        // the fee of giving tokens back comes out of what is given back
+        let fee = match icrc1_fee(ledger_canister_id).await {
+            Ok(fee) => fee,
+            Err(err) => return Ok(Err(err)),
+        };
+        let Ok(amount) = u64::try_from(-delta) else {
+            return Ok(Err(too_large()));
+        };
+        if amount <= fee {
+            return Ok(Ok(None));
+        }
+        let sent = send_icrc1_transfer(
+            ledger_canister_id,
+            supply.subaccount,
+            treasury,
+            amount - fee,
+            memo,
+            created_at_time,
+        )
+        .await?;
+        Ok(sent.map(Some))
+    } else {
+        Ok(Ok(None))
+    }
+}
+
+// pays the escrow out to the claimant (Completed) or back to the treasury (Refunded)
+pub async fn settle_bounty(bounty_id: &str, settled_status: BountyStatus) -> Result<Nat, String> {
+    let previous = with_state(|state| {
+        settle_bounty_controller(state, bounty_id, settled_status.clone(), ic_cdk::api::time())
+    })?;
+
+    let owner = match settled_status {
+        BountyStatus::Completed => match previous.claimed_by {
+            Some(claimant) => claimant,
+            None => {
+                with_state(|state| {
+                    state.bounties.insert(bounty_id.to_string(), previous.clone());
+                });
+                return Err(String::from("Claimed bounty has no claimant !"));
+            }
+        },
+        _ => ic_cdk::api::id(),
+    };
+    let to = Account {
+        owner,
+        subaccount: None,
+    };
+
+    let ledger_canister_id = with_state(|state| state.agent().token_ledger_id);
+
+    let result = icrc1_transfer(
+        ledger_canister_id,
+        Some(bounty_escrow_subaccount(bounty_id)),
+        to,
+        previous.tokens,
+        Some(bounty_id.as_bytes().to_vec()),
+        None,
+    )
+    .await;
+
+    with_state(|state| match &result {
+        Ok(block_index) => {
+            if let Some(mut bounty) = state.bounties.get(&bounty_id.to_string()) {
+                bounty.settled_block_index = Some(block_index.clone());
+                state.bounties.insert(bounty_id.to_string(), bounty);
+            }
+        }
+        Err(_) => {
+            state.bounties.insert(bounty_id.to_string(), previous);
+        }
+    });
+
+    result
+}
+
+// keeps the agent listing of the platform in line with the member groups
+async fn sync_members_with_backend() -> Result<(), String> {
+    let (neuropad_backend_id, members) = with_state(|state| {
+        (state.agent().neuropad_backend_id, state.agent().members.clone())
+    });
+
+    call_inter_canister::<Vec<Principal>, Result<(), String>>(
+        "sync_agent_members",
+        members,
+        neuropad_backend_id,
+    )
+    .await?
+}
+
+async fn transfer_treasury_tokens(
+    proposal_id: &str,
+    args: &TokenTransferPolicy,
+    created_at_time: Option<u64>,
+) -> Result<Nat, String> {
+    let ledger_canister_id = with_state(|state| state.agent().token_ledger_id);
+
+    icrc1_transfer(
+        ledger_canister_id,
+        None,
+        Account {
+            owner: args.to,
+            subaccount: None,
+        },
+        args.tokens,
+        Some(proposal_id.as_bytes().to_vec()),
+        created_at_time,
+    )
+    .await
+}
+
+// pushes the proposal summary to the platform index, the index is informative only
+// so a failed push is logged and does not fail the caller
+pub async fn sync_proposal_with_backend(proposal_id: &str) {
+    let (neuropad_backend_id, proposal) = with_state(|state| {
+        (
+            state.agent().neuropad_backend_id,
+            state.proposals.get(&proposal_id.to_string()),
+        )
+    });
+
+    let Some(proposal) = proposal else {
+        return;
+    };
+
+    let summary = ProposalValueStore {
+        associated_agent_canister_id: ic_cdk::api::id(),
+        proposal_id: proposal.proposal_id,
+        propsal_title: proposal.proposal_title,
+        proposal_description: proposal.proposal_description,
+        proposal_status: Some(proposal.proposal_status),
+        proposal_submitted_at: proposal.proposal_submitted_at,
+        proposal_expired_at: proposal.proposal_expired_at,
+        created_by: proposal.created_by,
+        proposal_entry: Some(proposal.proposal_entry),
+    };
+
+    let result = call_inter_canister::<ProposalValueStore, Result<String, String>>(
+        "sync_agent_proposal",
+        summary,
+        neuropad_backend_id,
+    )
+    .await
+    .and_then(|res| res);
+
+    if let Err(err) = result {
+        ic_cdk::println!("Failed to sync proposal {} with backend: {}", proposal_id, err);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::types::{AgentDetails, MemberGroup};
+use candid::Principal;
+use std::collections::HashSet;
+
+// members is the union of all groups, kept flat for the member guard
+fn refresh_members(agent: &mut AgentDetails) {
+    let mut seen: HashSet<Principal> = HashSet::new();
+    let mut members: Vec<Principal> = Vec::new();
+
+    for group in agent.member_groups.iter() {
+        for member in group.group_members.iter() {
+            if seen.insert(*member) {
+                members.push(*member);
+            }
+        }
+    }
+
+    agent.members_count = members.len() as u32;
+    agent.members = members;
+}
+
+// adding an existing member again is a no-op so that a retried execution is safe
+pub fn add_member_to_group(agent: &mut AgentDetails, group_name: &str, member: Principal) {
+    match agent
+        .member_groups
+        .iter_mut()
+        .find(|group| group.group_name == group_name)
+    {
+        Some(group) => {
+            if !group.group_members.contains(&member) {
+                group.group_members.push(member);
+            }
+        }
+        None => agent.member_groups.push(MemberGroup {
+            group_name: group_name.to_string(),
+            group_members: vec![member],
+        }),
+    }
+
+    refresh_members(agent);
+}
+
+pub fn remove_member_from_group(
+    agent: &mut AgentDetails,
+    group_name: &str,
+    member: Principal,
+) -> Result<(), String> {
+    let mut updated_agent = agent.clone();
+
+    let group = updated_agent
+        .member_groups
+        .iter_mut()
+        .find(|group| group.group_name == group_name)
+        .ok_or(String::from(crate::utils::GROUP_NOT_FOUND))?;
+
+    group.group_members.retain(|existing| *existing != member);
+
+    refresh_members(&mut updated_agent);
+
+    if updated_agent.members.is_empty() {
+        return Err(String::from("An agent cannot be left without members !"));
+    }
+
+    *agent = updated_agent;
+    Ok(())
+}
+
+pub fn is_group_member(agent: &AgentDetails, group_name: &str, member: &Principal) -> bool {
+    agent
+        .member_groups
+        .iter()
+        .any(|group| group.group_name == group_name && group.group_members.contains(member))
+}
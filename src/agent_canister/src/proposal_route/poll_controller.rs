@@ -0,0 +1,265 @@
+use crate::proposal_route::voting_power_of;
+use crate::state_handler::State;
+use crate::types::{CreatePoll, Poll, PollOptions, PollResult};
+use candid::Principal;
+use std::collections::HashSet;
+
+// members of a head-counted poll, which needs no stakes and so no token ledger
+pub fn head_count(members: Vec<Principal>) -> Vec<(Principal, u64)> {
+    let unique_members: HashSet<Principal> = members.into_iter().collect();
+    unique_members
+        .into_iter()
+        .map(|member| (member, 0))
+        .collect()
+}
+
+// to record a new poll, `voting_power` is the members' staked voting power snapshot for a
+// token-weighted poll and their `head_count` otherwise,
+// poll ids share the proposal counter so an id is unique across the agent
+pub fn create_poll_controller(
+    state: &mut State,
+    args: CreatePoll,
+    created_by: Principal,
+    voting_power: Vec<(Principal, u64)>,
+    now: u64,
+) -> Result<String, String> {
+    let place = state
+        .agent()
+        .proposal_entry
+        .iter()
+        .find(|place| place.place_name == args.proposal_entry)
+        .cloned()
+        .ok_or(String::from(crate::utils::PROPOSAL_PLACE_NOT_FOUND))?;
+
+    let proposer_power = state
+        .stakes
+        .get(&created_by)
+        .map(|balance| voting_power_of(&balance, now))
+        .unwrap_or(0);
+
+    if proposer_power < place.min_required_thredshold {
+        return Err(format!(
+            "{} Required: {}, available: {}",
+            crate::utils::INSUFFICIENT_VOTING_POWER,
+            place.min_required_thredshold,
+            proposer_power
+        ));
+    }
+
+    if args.token_weighted && voting_power.iter().all(|(_, power)| *power == 0) {
+        return Err(String::from(crate::utils::NO_VOTING_POWER));
+    }
+
+    if args.poll_query.trim().is_empty() {
+        return Err(String::from("Poll question cannot be empty !"));
+    }
+
+    if args.poll_options.len() < 2 || args.poll_options.len() > crate::utils::MAX_POLL_OPTIONS {
+        return Err(format!(
+            "A poll needs between 2 and {} options !",
+            crate::utils::MAX_POLL_OPTIONS
+        ));
+    }
+
+    let mut poll_options: Vec<PollOptions> = Vec::with_capacity(args.poll_options.len());
+    for (index, option) in args.poll_options.into_iter().enumerate() {
+        let option = option.trim().to_string();
+        if option.is_empty() {
+            return Err(String::from("Poll option cannot be empty !"));
+        }
+        if poll_options.iter().any(|other| other.option == option) {
+            return Err(format!("Duplicate poll option: {}", option));
+        }
+        poll_options.push(PollOptions {
+            option,
+            id: index.to_string(),
+            poll_approved_votes: 0,
+            approved_users: Vec::new(),
+        });
+    }
+
+    if args.proposal_expired_at <= now {
+        return Err(String::from("Poll expiry time must be in the future !"));
+    }
+
+    let poll_id = state.next_proposal_id();
+
+    let poll = Poll {
+        poll_id: poll_id.clone(),
+        poll_query: args.poll_query,
+        description: args.description,
+        poll_options,
+        proposal_entry: place.place_name,
+        token_weighted: args.token_weighted,
+        voting_power,
+        created_by,
+        created_at: now,
+        proposal_expired_at: args.proposal_expired_at,
+    };
+
+    state.polls.insert(poll_id.clone(), poll);
+
+    Ok(poll_id)
+}
+
+// one vote per member of the snapshot, weighted by its balance for token weighted polls
+pub fn vote_on_poll_controller(
+    state: &mut State,
+    poll_id: &str,
+    option_id: &str,
+    voter: Principal,
+    now: u64,
+) -> Result<(), String> {
+    let mut poll = state
+        .polls
+        .get(&poll_id.to_string())
+        .ok_or(String::from(crate::utils::POLL_NOT_FOUND))?;
+
+    if now >= poll.proposal_expired_at {
+        return Err(String::from(crate::utils::POLL_CLOSED));
+    }
+
+    if poll
+        .poll_options
+        .iter()
+        .any(|option| option.approved_users.contains(&voter))
+    {
+        return Err(String::from(crate::utils::ALREADY_VOTED));
+    }
+
+    let power = poll
+        .voting_power
+        .iter()
+        .find(|(member, _)| *member == voter)
+        .map(|(_, power)| *power)
+        .ok_or(String::from(crate::utils::NOT_A_MEMBER))?;
+
+    let weight = if poll.token_weighted { power } else { 1 };
+
+    if weight == 0 {
+        return Err(String::from(crate::utils::NO_VOTING_POWER));
+    }
+
+    let option = poll
+        .poll_options
+        .iter_mut()
+        .find(|option| option.id == option_id)
+        .ok_or(String::from(crate::utils::POLL_OPTION_NOT_FOUND))?;
+
+    option.approved_users.push(voter);
+    option.poll_approved_votes += weight;
+
+    state.polls.insert(poll_id.to_string(), poll);
+
+    Ok(())
+}
+
+pub fn poll_result(poll: Poll, now: u64) -> PollResult {
+    let total_votes = poll
+        .poll_options
+        .iter()
+        .map(|option| option.poll_approved_votes)
+        .sum();
+
+    let top_votes = poll
+        .poll_options
+        .iter()
+        .map(|option| option.poll_approved_votes)
+        .max()
+        .unwrap_or(0);
+
+    let mut leaders = poll
+        .poll_options
+        .iter()
+        .filter(|option| option.poll_approved_votes == top_votes);
+
+    let winning_option = match (leaders.next(), leaders.next()) {
+        (Some(option), None) if top_votes > 0 => Some(option.clone()),
+        _ => None,
+    };
+
+    PollResult {
+        is_open: now < poll.proposal_expired_at,
+        total_votes,
+        winning_option,
+        poll,
+    }
+}
+
+pub fn list_polls_controller(state: &State, start: u32, end: u32, now: u64) -> Vec<PollResult> {
+    let mut polls: Vec<Poll> = state.polls.iter().map(|(_, poll)| poll).collect();
+    polls.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+    polls
+        .into_iter()
+        .skip(start as usize)
+        .take(end.saturating_sub(start) as usize)
+        .map(|poll| poll_result(poll, now))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{poll, principal};
+    use crate::types::ProposalPlace;
+
+    fn create_args(token_weighted: bool) -> CreatePoll {
+        CreatePoll {
+            description: String::new(),
+            poll_options: vec![String::from("yes"), String::from("no")],
+            proposal_expired_at: 100,
+            proposal_entry: String::from("General"),
+            poll_query: String::from("question"),
+            token_weighted,
+        }
+    }
+
+    #[test]
+    fn picks_the_single_leader() {
+        let result = poll_result(poll(&[1, 3, 2]), 10);
+
+        assert_eq!(result.total_votes, 6);
+        assert_eq!(result.winning_option.map(|option| option.id), Some(String::from("1")));
+        assert!(result.is_open);
+    }
+
+    #[test]
+    fn has_no_winner_on_a_tie() {
+        let result = poll_result(poll(&[3, 1, 3]), 10);
+
+        assert_eq!(result.total_votes, 7);
+        assert!(result.winning_option.is_none());
+    }
+
+    #[test]
+    fn has_no_winner_without_votes() {
+        assert!(poll_result(poll(&[0]), 10).winning_option.is_none());
+        assert!(poll_result(poll(&[]), 10).winning_option.is_none());
+    }
+
+    #[test]
+    fn closes_at_expiry() {
+        assert!(!poll_result(poll(&[1]), 100).is_open);
+    }
+
+    #[test]
+    fn counts_heads_without_a_ledger() {
+        let mut state = State::new();
+        state.update_agent(|agent| {
+            agent.proposal_entry.push(ProposalPlace {
+                place_name: String::from("General"),
+                ..Default::default()
+            })
+        });
+        let members = head_count(vec![principal(1), principal(2), principal(1)]);
+
+        let poll_id =
+            create_poll_controller(&mut state, create_args(false), principal(1), members, 10)
+                .unwrap();
+        assert!(vote_on_poll_controller(&mut state, &poll_id, "0", principal(2), 20).is_ok());
+        assert_eq!(state.polls.get(&poll_id).unwrap().poll_options[0].poll_approved_votes, 1);
+        // voting twice stays one head
+        assert!(vote_on_poll_controller(&mut state, &poll_id, "1", principal(2), 20).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use crate::state_handler::State;
+use crate::types::{ProposalInput, ProposalPlace, ProposalState, Proposals, VoteParam};
+use candid::Principal;
+
+// to record a new proposal, `voting_power` is the members' staked voting power snapshot
+pub fn create_proposal_controller(
+    state: &mut State,
+    args: ProposalInput,
+    created_by: Principal,
+    voting_power: Vec<(Principal, u64)>,
+    now: u64,
+) -> Result<String, String> {
+    let place = state
+        .agent()
+        .proposal_entry
+        .iter()
+        .find(|place| place.place_name == args.proposal_entry)
+        .cloned()
+        .ok_or(String::from(crate::utils::PROPOSAL_PLACE_NOT_FOUND))?;
+
+    let proposer_power = voting_power
+        .iter()
+        .find(|(member, _)| *member == created_by)
+        .map(|(_, power)| *power)
+        .unwrap_or(0);
+
+    if proposer_power < place.min_required_thredshold {
+        return Err(format!(
+            "{} Required: {}, available: {}",
+            crate::utils::INSUFFICIENT_VOTING_POWER,
+            place.min_required_thredshold,
+            proposer_power
+        ));
+    }
+
+    let total_voting_power: u64 = voting_power.iter().map(|(_, power)| *power).sum();
+
+    if total_voting_power == 0 {
+        return Err(String::from(crate::utils::NO_VOTING_POWER));
+    }
+
+    let proposal_expired_at = args
+        .proposal_expired_at
+        .unwrap_or(now.saturating_add(crate::utils::PROPOSAL_DURATION));
+
+    if proposal_expired_at <= now {
+        return Err(String::from("Proposal expiry time must be in the future !"));
+    }
+
+    let proposal_id = state.next_proposal_id();
+
+    let new_proposal = Proposals {
+        proposal_id: proposal_id.clone(),
+        proposal_title: args.proposal_title,
+        proposal_description: args.proposal_description,
+        proposal_status: ProposalState::Open,
+        proposal_submitted_at: now,
+        proposal_expired_at,
+        proposal_approved_votes: 0,
+        approved_votes_list: Vec::new(),
+        proposal_rejected_votes: 0,
+        rejected_votes_list: Vec::new(),
+        quorum_percentage: place.quorum_percentage,
+        approval_percentage: place.approval_percentage,
+        total_voting_power,
+        voting_power,
+        created_by,
+        proposal_type: args.proposal_type,
+        proposal_entry: place.place_name,
+        executed_block_index: None,
+        execution_error: None,
+        execution_created_at: None,
+    };
+
+    state.proposals.insert(proposal_id.clone(), new_proposal);
+
+    Ok(proposal_id)
+}
+
+// moves an open proposal forward once its outcome is settled or its expiry is hit,
+// vote counts are in snapshot voting power, not heads
+pub fn refresh_proposal_state(proposal: &mut Proposals, now: u64) {
+    if proposal.proposal_status != ProposalState::Open {
+        return;
+    }
+
+    let total = proposal.total_voting_power as u128;
+    let approved = proposal.proposal_approved_votes as u128;
+    let cast = approved + proposal.proposal_rejected_votes as u128;
+    let remaining = total.saturating_sub(cast);
+    let quorum = proposal.quorum_percentage as u128;
+    let approval = proposal.approval_percentage as u128;
+
+    let quorum_reached = cast * 100 >= quorum * total;
+
+    if quorum_reached && approved * 100 >= approval * total {
+        // approved even if every remaining member votes No
+        proposal.proposal_status = ProposalState::Accepted;
+    } else if (approved + remaining) * 100 < approval * total {
+        // rejected even if every remaining member votes Yes
+        proposal.proposal_status = ProposalState::Rejected;
+    } else if now >= proposal.proposal_expired_at {
+        proposal.proposal_status = if !quorum_reached {
+            ProposalState::Expired
+        } else if approved * 100 >= approval * cast {
+            ProposalState::Accepted
+        } else {
+            ProposalState::Rejected
+        };
+    }
+}
+
+pub fn vote_controller(
+    state: &mut State,
+    proposal_id: &str,
+    voter: Principal,
+    vote: VoteParam,
+    now: u64,
+) -> Result<ProposalState, String> {
+    let mut proposal = state
+        .proposals
+        .get(&proposal_id.to_string())
+        .ok_or(String::from(crate::utils::PROPOSAL_NOT_FOUND))?;
+
+    refresh_proposal_state(&mut proposal, now);
+
+    if proposal.proposal_status != ProposalState::Open {
+        state.proposals.insert(proposal_id.to_string(), proposal);
+        return Err(String::from(crate::utils::PROPOSAL_NOT_OPEN));
+    }
+
+    if proposal.approved_votes_list.contains(&voter)
+        || proposal.rejected_votes_list.contains(&voter)
+    {
+        return Err(String::from(crate::utils::ALREADY_VOTED));
+    }
+
+    let weight = proposal
+        .voting_power
+        .iter()
+        .find(|(member, _)| *member == voter)
+        .map(|(_, power)| *power)
+        .unwrap_or(0);
+
+    if weight == 0 {
+        return Err(String::from(crate::utils::NO_VOTING_POWER));
+    }
+
+    match vote {
+        VoteParam::Yes => {
+            proposal.approved_votes_list.push(voter);
+            proposal.proposal_approved_votes += weight;
+        }
+        VoteParam::No => {
+            proposal.rejected_votes_list.push(voter);
+            proposal.proposal_rejected_votes += weight;
+        }
+    }
+
+    refresh_proposal_state(&mut proposal, now);
+
+    let status = proposal.proposal_status.clone();
+    state.proposals.insert(proposal_id.to_string(), proposal);
+
+    Ok(status)
+}
+
+// proposals as they would look right now, without persisting expiry changes
+pub fn get_proposal_controller(state: &State, proposal_id: &str, now: u64) -> Option<Proposals> {
+    state.proposals.get(&proposal_id.to_string()).map(|mut proposal| {
+        refresh_proposal_state(&mut proposal, now);
+        proposal
+    })
+}
+
+pub fn list_proposals_controller(state: &State, start: u32, end: u32, now: u64) -> Vec<Proposals> {
+    let mut proposals: Vec<Proposals> = state.proposals.iter().map(|(_, proposal)| proposal).collect();
+    proposals.sort_by(|a, b| b.proposal_submitted_at.cmp(&a.proposal_submitted_at));
+
+    proposals
+        .into_iter()
+        .skip(start as usize)
+        .take(end.saturating_sub(start) as usize)
+        .map(|mut proposal| {
+            refresh_proposal_state(&mut proposal, now);
+            proposal
+        })
+        .collect()
+}
+
+pub fn validate_proposal_places(places: &[ProposalPlace]) -> Result<(), String> {
+    if places.is_empty() {
+        return Err(String::from("At least one proposal entry is required !"));
+    }
+
+    for (index, place) in places.iter().enumerate() {
+        if place.place_name.trim().is_empty() {
+            return Err(String::from("Proposal entry name cannot be empty !"));
+        }
+        if places[..index].iter().any(|other| other.place_name == place.place_name) {
+            return Err(format!("Duplicate proposal entry: {}", place.place_name));
+        }
+        if place.quorum_percentage > 100 {
+            return Err(format!("Invalid quorum for proposal entry: {}", place.place_name));
+        }
+        if place.approval_percentage == 0 || place.approval_percentage > 100 {
+            return Err(format!("Invalid approval ratio for proposal entry: {}", place.place_name));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::proposal;
+
+    fn refreshed(mut proposal: Proposals, now: u64) -> ProposalState {
+        refresh_proposal_state(&mut proposal, now);
+        proposal.proposal_status
+    }
+
+    #[test]
+    fn accepts_early_once_approval_is_out_of_reach_of_no_votes() {
+        assert_eq!(refreshed(proposal(100, 51, 0), 10), ProposalState::Accepted);
+        assert_eq!(refreshed(proposal(100, 50, 0), 10), ProposalState::Open);
+    }
+
+    #[test]
+    fn rejects_early_once_approval_is_out_of_reach_of_yes_votes() {
+        assert_eq!(refreshed(proposal(100, 0, 50), 10), ProposalState::Rejected);
+        assert_eq!(refreshed(proposal(100, 0, 49), 10), ProposalState::Open);
+    }
+
+    #[test]
+    fn settles_on_cast_votes_at_expiry() {
+        // quorum reached, 30 of 50 cast votes approve
+        assert_eq!(refreshed(proposal(100, 30, 20), 100), ProposalState::Accepted);
+        assert_eq!(refreshed(proposal(100, 20, 30), 100), ProposalState::Rejected);
+    }
+
+    #[test]
+    fn expires_without_quorum() {
+        assert_eq!(refreshed(proposal(100, 30, 10), 100), ProposalState::Expired);
+    }
+
+    #[test]
+    fn leaves_settled_proposals_alone() {
+        let mut settled = proposal(100, 0, 100);
+        settled.proposal_status = ProposalState::Unreachable;
+        assert_eq!(refreshed(settled, 100), ProposalState::Unreachable);
+    }
+}
@@ -0,0 +1,429 @@
+use crate::state_handler::State;
+use crate::types::{PendingCompensation, SaleConfig, SaleQuote, SaleTrade, TradeSide};
+use candid::{Nat, Principal};
+use icrc_ledger_types::icrc1::account::Subaccount;
+use sha2::{Digest, Sha256};
+
+// subaccount of the agent canister on the NeuroPad ledger that backs the curve
+pub fn sale_reserve_subaccount() -> Subaccount {
+    let mut hasher = Sha256::new();
+    hasher.update(b"sale-reserve");
+
+    let mut subaccount = [0u8; 32];
+    subaccount.copy_from_slice(&hasher.finalize());
+    subaccount
+}
+
+// subaccount of the agent canister on the agent token ledger that holds the tokens
+// the curve can still sell, buys are paid out of it and sells go back into it
+pub fn sale_supply_subaccount() -> Subaccount {
+    let mut hasher = Sha256::new();
+    hasher.update(b"sale-supply");
+
+    let mut subaccount = [0u8; 32];
+    subaccount.copy_from_slice(&hasher.finalize());
+    subaccount
+}
+
+pub fn validate_sale_config(state: &State, config: &SaleConfig) -> Result<(), String> {
+    if config.payment_ledger_id == Principal::anonymous() {
+        return Err(String::from("Payment ledger of the sale is required !"));
+    }
+    if config.base_price == 0 && config.slope == 0 {
+        return Err(String::from("Sale price cannot be zero !"));
+    }
+    if config.sale_supply < state.sale.get().tokens_sold {
+        return Err(String::from("Sale supply cannot be lower than the tokens already sold !"));
+    }
+    // the reserve lives on the payment ledger, it cannot move to another one
+    if let Some(current) = state.sale.get().config.as_ref() {
+        if current.payment_ledger_id != config.payment_ledger_id
+            && state.sale.get().reserve_balance > 0
+        {
+            return Err(String::from(
+                "Payment ledger cannot change while the sale holds a reserve !",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// agent tokens to move into (positive) or out of (negative) the sale supply subaccount
+// before `config` applies
+pub fn sale_supply_delta(state: &State, config: &SaleConfig) -> i128 {
+    config.sale_supply as i128 - state.sale.get().funded_supply.unwrap_or(0) as i128
+}
+
+// counts the supply of `config` as funded before its tokens move, so that a proposal running
+// at the same time only moves what is left; returns the tokens to move
+pub fn reserve_sale_supply(state: &mut State, config: &SaleConfig) -> Result<i128, String> {
+    validate_sale_config(state, config)?;
+    let delta = sale_supply_delta(state, config);
+    state.update_sale(|sale| sale.funded_supply = Some(config.sale_supply));
+    Ok(delta)
+}
+
+// undoes a reservation whose tokens did not move
+pub fn release_sale_supply(state: &mut State, delta: i128) {
+    state.update_sale(|sale| {
+        let funded = sale.funded_supply.unwrap_or(0) as i128 - delta;
+        sale.funded_supply = Some(funded.clamp(0, u64::MAX as i128) as u64);
+    });
+}
+
+// applies the config once the supply subaccount was funded for it
+pub fn configure_sale_controller(state: &mut State, config: SaleConfig) -> Result<(), String> {
+    validate_sale_config(state, &config)?;
+    state.update_sale(|sale| sale.config = Some(config));
+    Ok(())
+}
+
+// area under the curve between `from` and `from + amount` tokens sold,
+// buys round up and sells round down so the reserve always covers the curve
+fn curve_area(config: &SaleConfig, from: u64, amount: u64, round_up: bool) -> Result<u64, String> {
+    let scale = crate::utils::CURVE_PRICE_SCALE;
+    let overflow = || String::from("Trade amount is too large !");
+
+    let (from, amount) = (from as u128, amount as u128);
+
+    // (from + amount)^2 - from^2
+    let squares = amount
+        .checked_mul(from.checked_mul(2).ok_or_else(overflow)?)
+        .and_then(|value| value.checked_add(amount.checked_mul(amount)?))
+        .ok_or_else(overflow)?;
+
+    let numerator = (config.base_price as u128)
+        .checked_mul(amount)
+        .and_then(|value| value.checked_mul(2 * scale))
+        .and_then(|value| value.checked_add((config.slope as u128).checked_mul(squares)?))
+        .ok_or_else(overflow)?;
+    let denominator = 2 * scale * scale;
+
+    let area = if round_up {
+        (numerator + denominator - 1) / denominator
+    } else {
+        numerator / denominator
+    };
+
+    u64::try_from(area).map_err(|_| overflow())
+}
+
+fn open_sale(state: &State) -> Result<SaleConfig, String> {
+    state
+        .sale
+        .get()
+        .config
+        .clone()
+        .filter(|config| config.is_open)
+        .ok_or(String::from(crate::utils::SALE_NOT_OPEN))
+}
+
+pub fn quote_buy(state: &State, token_amount: u64) -> Result<SaleQuote, String> {
+    let config = open_sale(state)?;
+    let tokens_sold = state.sale.get().tokens_sold;
+
+    if token_amount == 0 {
+        return Err(String::from("Token amount must be greater than zero !"));
+    }
+    let tokens_sold_after = tokens_sold
+        .checked_add(token_amount)
+        .filter(|after| *after <= config.sale_supply)
+        .ok_or(String::from("Not enough tokens left in the sale !"))?;
+
+    Ok(SaleQuote {
+        token_amount,
+        payment_amount: curve_area(&config, tokens_sold, token_amount, true)?,
+        tokens_sold_after,
+    })
+}
+
+pub fn quote_sell(state: &State, token_amount: u64) -> Result<SaleQuote, String> {
+    let config = open_sale(state)?;
+    let tokens_sold = state.sale.get().tokens_sold;
+
+    if token_amount == 0 {
+        return Err(String::from("Token amount must be greater than zero !"));
+    }
+    if token_amount > tokens_sold {
+        return Err(String::from("Cannot sell back more tokens than the sale sold !"));
+    }
+    let tokens_sold_after = tokens_sold - token_amount;
+
+    Ok(SaleQuote {
+        token_amount,
+        payment_amount: curve_area(&config, tokens_sold_after, token_amount, false)?,
+        tokens_sold_after,
+    })
+}
+
+// moves the curve before the ledger calls so that concurrent trades are priced after it,
+// `release_buy` undoes it when the trade does not go through
+pub fn reserve_buy(
+    state: &mut State,
+    token_amount: u64,
+    max_cost: u64,
+) -> Result<(SaleConfig, SaleQuote), String> {
+    let quote = quote_buy(state, token_amount)?;
+    if quote.payment_amount > max_cost {
+        return Err(format!(
+            "Price moved, buying costs {} which is above the maximum of {} !",
+            quote.payment_amount, max_cost
+        ));
+    }
+
+    state.update_sale(|sale| sale.tokens_sold = quote.tokens_sold_after);
+    Ok((open_sale(state)?, quote))
+}
+
+pub fn release_buy(state: &mut State, token_amount: u64) {
+    state.update_sale(|sale| sale.tokens_sold = sale.tokens_sold.saturating_sub(token_amount));
+}
+
+pub fn reserve_sell(
+    state: &mut State,
+    token_amount: u64,
+    min_return: u64,
+) -> Result<(SaleConfig, SaleQuote), String> {
+    let quote = quote_sell(state, token_amount)?;
+    if quote.payment_amount < min_return {
+        return Err(format!(
+            "Price moved, selling returns {} which is below the minimum of {} !",
+            quote.payment_amount, min_return
+        ));
+    }
+    if quote.payment_amount > state.sale.get().reserve_balance {
+        return Err(String::from("Sale reserve cannot cover this sell !"));
+    }
+
+    let config = open_sale(state)?;
+    state.update_sale(|sale| {
+        sale.tokens_sold = quote.tokens_sold_after;
+        sale.reserve_balance -= quote.payment_amount;
+    });
+    Ok((config, quote))
+}
+
+pub fn release_sell(state: &mut State, quote: &SaleQuote) {
+    state.update_sale(|sale| {
+        sale.tokens_sold = sale.tokens_sold.saturating_add(quote.token_amount);
+        sale.reserve_balance = sale.reserve_balance.saturating_add(quote.payment_amount);
+    });
+}
+
+pub fn record_trade(
+    state: &mut State,
+    trader: Principal,
+    side: TradeSide,
+    quote: &SaleQuote,
+    token_block_index: Nat,
+    payment_block_index: Nat,
+    now: u64,
+) -> SaleTrade {
+    if side == TradeSide::Buy {
+        state.update_sale(|sale| {
+            sale.reserve_balance = sale.reserve_balance.saturating_add(quote.payment_amount)
+        });
+    }
+
+    let trade_id = state
+        .sale_trades
+        .last_key_value()
+        .map(|(trade_id, _)| trade_id + 1)
+        .unwrap_or(1);
+
+    let trade = SaleTrade {
+        trade_id,
+        trader,
+        side,
+        token_amount: quote.token_amount,
+        payment_amount: quote.payment_amount,
+        tokens_sold_after: quote.tokens_sold_after,
+        token_block_index,
+        payment_block_index,
+        traded_at: now,
+    };
+    state.sale_trades.insert(trade_id, trade.clone());
+
+    trade
+}
+
+// a buy is refunded from the reserve, a sell gets its tokens back from the sale supply
+pub fn compensation_subaccount(side: &TradeSide) -> Subaccount {
+    match side {
+        TradeSide::Buy => sale_reserve_subaccount(),
+        TradeSide::Sell => sale_supply_subaccount(),
+    }
+}
+
+// keeps a compensation that could not be paid, returns the id its trader retries it with
+pub fn record_compensation(state: &mut State, mut compensation: PendingCompensation) -> u64 {
+    compensation.compensation_id = state
+        .compensations
+        .last_key_value()
+        .map(|(compensation_id, _)| compensation_id + 1)
+        .unwrap_or(1);
+    compensation.retrying = false;
+
+    let compensation_id = compensation.compensation_id;
+    state.compensations.insert(compensation_id, compensation);
+    compensation_id
+}
+
+// marks the compensation as being paid so a second call cannot pay it again meanwhile
+pub fn claim_compensation(
+    state: &mut State,
+    compensation_id: u64,
+    caller: Principal,
+) -> Result<PendingCompensation, String> {
+    let mut compensation = state
+        .compensations
+        .get(&compensation_id)
+        .filter(|compensation| compensation.trader == caller)
+        .ok_or(String::from(crate::utils::COMPENSATION_NOT_FOUND))?;
+
+    if compensation.retrying {
+        return Err(String::from("Compensation is already being paid !"));
+    }
+
+    compensation.retrying = true;
+    state.compensations.insert(compensation_id, compensation.clone());
+    Ok(compensation)
+}
+
+// drops a paid compensation, a refused one gets a fresh created_at_time on its next try and
+// one with an unknown outcome keeps the created_at_time it was sent with
+pub fn finish_compensation(
+    state: &mut State,
+    compensation_id: u64,
+    created_at_time: u64,
+    result: &Result<Result<(), String>, String>,
+) {
+    let Some(mut compensation) = state.compensations.get(&compensation_id) else {
+        return;
+    };
+
+    match result {
+        Ok(Ok(())) => {
+            state.compensations.remove(&compensation_id);
+            return;
+        }
+        Ok(Err(err)) => {
+            compensation.created_at_time = None;
+            compensation.error = err.clone();
+        }
+        Err(err) => {
+            compensation.created_at_time = Some(created_at_time);
+            compensation.error = err.clone();
+        }
+    }
+
+    compensation.retrying = false;
+    state.compensations.insert(compensation_id, compensation);
+}
+
+// newest trades first
+pub fn sale_history(state: &State, start: u32, end: u32) -> Vec<SaleTrade> {
+    let mut trades: Vec<SaleTrade> = state.sale_trades.iter().map(|(_, trade)| trade).collect();
+    trades.reverse();
+
+    trades
+        .into_iter()
+        .skip(start as usize)
+        .take(end.saturating_sub(start) as usize)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{principal, sale_config};
+
+    const SCALE: u64 = crate::utils::CURVE_PRICE_SCALE as u64;
+
+    #[test]
+    fn prices_a_flat_curve_by_amount() {
+        assert_eq!(curve_area(&sale_config(SCALE, 0), 0, 100, true), Ok(100));
+        assert_eq!(curve_area(&sale_config(SCALE, 0), 500, 100, false), Ok(100));
+    }
+
+    #[test]
+    fn prices_a_linear_curve_by_area() {
+        // the price at x tokens sold is x
+        let linear = sale_config(0, SCALE * SCALE);
+
+        assert_eq!(curve_area(&linear, 0, 10, true), Ok(50));
+        assert_eq!(curve_area(&linear, 10, 10, true), Ok(150));
+        assert_eq!(curve_area(&linear, 0, 20, true), Ok(200));
+    }
+
+    #[test]
+    fn rounds_buys_up_and_sells_down() {
+        let cheap = sale_config(1, 0);
+
+        assert_eq!(curve_area(&cheap, 0, 1, true), Ok(1));
+        assert_eq!(curve_area(&cheap, 0, 1, false), Ok(0));
+    }
+
+    #[test]
+    fn rejects_overflowing_trades() {
+        assert!(curve_area(&sale_config(0, u64::MAX), u64::MAX, u64::MAX, true).is_err());
+        assert!(curve_area(&sale_config(u64::MAX, 0), 0, u64::MAX, true).is_err());
+    }
+
+    #[test]
+    fn funds_the_supply_difference() {
+        let mut state = State::new();
+        assert_eq!(sale_supply_delta(&state, &sale_config(SCALE, 0)), 1000);
+
+        state.update_sale(|sale| sale.funded_supply = Some(1200));
+        assert_eq!(sale_supply_delta(&state, &sale_config(SCALE, 0)), -200);
+    }
+
+    #[test]
+    fn reserves_the_supply_before_it_moves() {
+        let mut state = State::new();
+        let mut larger = sale_config(SCALE, 0);
+        larger.sale_supply = 1500;
+
+        assert_eq!(reserve_sale_supply(&mut state, &sale_config(SCALE, 0)), Ok(1000));
+        // a second proposal only moves what the first one does not
+        assert_eq!(reserve_sale_supply(&mut state, &larger), Ok(500));
+
+        release_sale_supply(&mut state, 1000);
+        assert_eq!(state.sale.get().funded_supply, Some(500));
+    }
+
+    #[test]
+    fn pays_a_compensation_once() {
+        let mut state = State::new();
+        let compensation_id = record_compensation(
+            &mut state,
+            PendingCompensation {
+                compensation_id: 0,
+                trader: principal(1),
+                side: TradeSide::Sell,
+                ledger_id: principal(2),
+                amount: 100,
+                created_at_time: Some(5),
+                error: String::new(),
+                recorded_at: 5,
+                retrying: false,
+            },
+        );
+
+        assert!(claim_compensation(&mut state, compensation_id, principal(3)).is_err());
+        assert!(claim_compensation(&mut state, compensation_id, principal(1)).is_ok());
+        // a second call while the first one is paying is refused
+        assert!(claim_compensation(&mut state, compensation_id, principal(1)).is_err());
+
+        finish_compensation(&mut state, compensation_id, 7, &Ok(Err(String::from("refused"))));
+        let compensation = state.compensations.get(&compensation_id).unwrap();
+        assert_eq!(compensation.created_at_time, None);
+        assert!(!compensation.retrying);
+
+        assert!(claim_compensation(&mut state, compensation_id, principal(1)).is_ok());
+        finish_compensation(&mut state, compensation_id, 9, &Ok(Ok(())));
+        assert!(state.compensations.get(&compensation_id).is_none());
+    }
+}
@@ -0,0 +1,193 @@
+use crate::state_handler::State;
+use crate::types::{AccountBalance, StakeStatus};
+use candid::Principal;
+use icrc_ledger_types::icrc1::account::Subaccount;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+// subaccount of the agent canister on the agent token ledger that holds the stake of `owner`
+pub fn staking_subaccount(owner: &Principal) -> Subaccount {
+    let mut hasher = Sha256::new();
+    hasher.update(b"stake:");
+    hasher.update(owner.as_slice());
+
+    let mut subaccount = [0u8; 32];
+    subaccount.copy_from_slice(&hasher.finalize());
+    subaccount
+}
+
+// `lock_seconds` is checked before any tokens move, so a stake never fails after the transfer
+pub fn validate_lock(
+    state: &State,
+    owner: Principal,
+    lock_seconds: Option<u64>,
+    now: u64,
+) -> Result<Option<u64>, String> {
+    let Some(lock_seconds) = lock_seconds else {
+        return Ok(None);
+    };
+
+    let lock_duration = lock_seconds.saturating_mul(1_000_000_000);
+    if lock_duration == 0 || lock_duration > crate::utils::MAX_LOCK_DURATION {
+        return Err(format!(
+            "Lock duration must be between 1 and {} seconds !",
+            crate::utils::MAX_LOCK_DURATION / 1_000_000_000
+        ));
+    }
+
+    if let Some(balance) = state.stakes.get(&owner) {
+        if now.saturating_add(lock_duration) < balance.locked_until {
+            return Err(String::from("A running lock can only be extended !"));
+        }
+    }
+
+    Ok(Some(lock_duration))
+}
+
+pub fn record_stake(
+    state: &mut State,
+    owner: Principal,
+    amount: u64,
+    lock_duration: Option<u64>,
+    now: u64,
+) -> AccountBalance {
+    let mut balance = state.stakes.get(&owner).unwrap_or(AccountBalance {
+        id: owner,
+        staked: 0,
+        lock_duration: 0,
+        locked_until: 0,
+        updated_at: now,
+    });
+
+    balance.staked = balance.staked.saturating_add(amount);
+    if let Some(lock_duration) = lock_duration {
+        balance.lock_duration = lock_duration;
+        balance.locked_until = balance.locked_until.max(now.saturating_add(lock_duration));
+    }
+    balance.updated_at = now;
+
+    state.stakes.insert(owner, balance.clone());
+    balance
+}
+
+// takes `amount` off the stake before the transfer runs, `release_unstake` puts it back
+// when the transfer fails
+pub fn reserve_unstake(
+    state: &mut State,
+    owner: Principal,
+    amount: u64,
+    now: u64,
+) -> Result<(), String> {
+    let mut balance = state
+        .stakes
+        .get(&owner)
+        .ok_or(String::from(crate::utils::STAKE_NOT_FOUND))?;
+
+    if amount == 0 || amount > balance.staked {
+        return Err(format!("Unstake amount must be between 1 and {} !", balance.staked));
+    }
+    if now < balance.locked_until {
+        return Err(format!("Stake is locked until {} !", balance.locked_until));
+    }
+
+    balance.staked -= amount;
+    balance.updated_at = now;
+
+    if balance.staked == 0 {
+        state.stakes.remove(&owner);
+    } else {
+        state.stakes.insert(owner, balance);
+    }
+
+    Ok(())
+}
+
+pub fn release_unstake(state: &mut State, owner: Principal, amount: u64, now: u64) {
+    record_stake(state, owner, amount, None, now);
+}
+
+// staked tokens, boosted while a lock is running
+pub fn voting_power_of(balance: &AccountBalance, now: u64) -> u64 {
+    if now >= balance.locked_until {
+        return balance.staked;
+    }
+
+    let boost = crate::utils::MAX_LOCK_BOOST_PERCENTAGE as u128 * balance.lock_duration as u128
+        / crate::utils::MAX_LOCK_DURATION as u128;
+    let power = balance.staked as u128 * (100 + boost) / 100;
+
+    u64::try_from(power).unwrap_or(u64::MAX)
+}
+
+pub fn stake_status(balance: AccountBalance, now: u64) -> StakeStatus {
+    StakeStatus {
+        voting_power: voting_power_of(&balance, now),
+        balance,
+    }
+}
+
+// voting power of the given members at this moment, taken from their stakes
+pub fn voting_power_snapshot(
+    state: &State,
+    members: Vec<Principal>,
+    now: u64,
+) -> Result<Vec<(Principal, u64)>, String> {
+    if state.agent().token_ledger_id == Principal::anonymous() {
+        return Err(String::from(crate::utils::LEDGER_NOT_SET));
+    }
+
+    let unique_members: HashSet<Principal> = members.into_iter().collect();
+
+    Ok(unique_members
+        .into_iter()
+        .map(|member| {
+            let power = state
+                .stakes
+                .get(&member)
+                .map(|balance| voting_power_of(&balance, now))
+                .unwrap_or(0);
+            (member, power)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{principal, stake};
+
+    const MAX_LOCK: u64 = crate::utils::MAX_LOCK_DURATION;
+
+    #[test]
+    fn counts_unlocked_stake_as_is() {
+        assert_eq!(voting_power_of(&stake(100, 0, 0), 10), 100);
+        // an expired lock no longer boosts
+        assert_eq!(voting_power_of(&stake(100, MAX_LOCK, 50), 50), 100);
+    }
+
+    #[test]
+    fn boosts_running_locks_by_their_duration() {
+        assert_eq!(voting_power_of(&stake(100, MAX_LOCK, 50), 10), 200);
+        assert_eq!(voting_power_of(&stake(100, MAX_LOCK / 2, 50), 10), 150);
+    }
+
+    #[test]
+    fn caps_boosted_power_at_u64_max() {
+        assert_eq!(voting_power_of(&stake(u64::MAX, MAX_LOCK, 50), 10), u64::MAX);
+    }
+
+    #[test]
+    fn snapshots_each_member_once() {
+        let mut state = State::new();
+        let (staker, idle) = (principal(1), principal(2));
+
+        assert!(voting_power_snapshot(&state, vec![staker], 10).is_err());
+
+        state.update_agent(|agent| agent.token_ledger_id = Principal::management_canister());
+        state.stakes.insert(staker, stake(100, 0, 0));
+
+        let mut snapshot = voting_power_snapshot(&state, vec![staker, idle, staker], 10).unwrap();
+        snapshot.sort();
+        assert_eq!(snapshot, vec![(staker, 100), (idle, 0)]);
+    }
+}
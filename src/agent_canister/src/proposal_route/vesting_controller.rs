@@ -0,0 +1,133 @@
+use crate::state_handler::State;
+use crate::types::{VestingAllocation, VestingSchedule, VestingStatus};
+use candid::Principal;
+use icrc_ledger_types::icrc1::account::Subaccount;
+use sha2::{Digest, Sha256};
+
+// the backend mints vesting allocations to the same subaccounts when it creates the ledger
+pub fn vesting_subaccount(beneficiary: &Principal) -> Subaccount {
+    let mut hasher = Sha256::new();
+    hasher.update(b"vesting:");
+    hasher.update(beneficiary.as_slice());
+
+    let mut subaccount = [0u8; 32];
+    subaccount.copy_from_slice(&hasher.finalize());
+    subaccount
+}
+
+pub fn create_vesting_schedules(state: &mut State, allocations: Vec<VestingAllocation>, now: u64) {
+    for allocation in allocations {
+        state.vesting_schedules.insert(
+            allocation.beneficiary,
+            VestingSchedule {
+                beneficiary: allocation.beneficiary,
+                total_amount: allocation.amount,
+                claimed_amount: 0,
+                start_at: now,
+                cliff_duration: allocation.cliff_seconds.saturating_mul(1_000_000_000),
+                vesting_duration: allocation.vesting_seconds.saturating_mul(1_000_000_000),
+                subaccount: vesting_subaccount(&allocation.beneficiary).to_vec(),
+            },
+        );
+    }
+}
+
+pub fn vested_amount(schedule: &VestingSchedule, now: u64) -> u64 {
+    let elapsed = now.saturating_sub(schedule.start_at);
+
+    if elapsed < schedule.cliff_duration {
+        return 0;
+    }
+    if elapsed >= schedule.vesting_duration {
+        return schedule.total_amount;
+    }
+
+    (schedule.total_amount as u128 * elapsed as u128 / schedule.vesting_duration as u128) as u64
+}
+
+pub fn vesting_status(schedule: VestingSchedule, now: u64) -> VestingStatus {
+    let vested = vested_amount(&schedule, now);
+
+    VestingStatus {
+        vested_amount: vested,
+        unvested_amount: schedule.total_amount - vested,
+        claimable_amount: vested.saturating_sub(schedule.claimed_amount),
+        schedule,
+    }
+}
+
+// marks everything vested so far as claimed before the transfer runs,
+// `release_claim` gives it back when the transfer fails
+pub fn reserve_claim(state: &mut State, beneficiary: Principal, now: u64) -> Result<u64, String> {
+    let mut schedule = state
+        .vesting_schedules
+        .get(&beneficiary)
+        .ok_or(String::from(crate::utils::VESTING_NOT_FOUND))?;
+
+    let claimable = vested_amount(&schedule, now).saturating_sub(schedule.claimed_amount);
+    if claimable == 0 {
+        return Err(String::from("Nothing has vested yet !"));
+    }
+
+    schedule.claimed_amount += claimable;
+    state.vesting_schedules.insert(beneficiary, schedule);
+
+    Ok(claimable)
+}
+
+pub fn release_claim(state: &mut State, beneficiary: Principal, amount: u64) {
+    if let Some(mut schedule) = state.vesting_schedules.get(&beneficiary) {
+        schedule.claimed_amount = schedule.claimed_amount.saturating_sub(amount);
+        state.vesting_schedules.insert(beneficiary, schedule);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::vesting_schedule;
+
+    #[test]
+    fn vests_nothing_before_the_cliff() {
+        let schedule = vesting_schedule(10, 100);
+
+        assert_eq!(vested_amount(&schedule, 0), 0);
+        assert_eq!(vested_amount(&schedule, 109), 0);
+    }
+
+    #[test]
+    fn vests_linearly_from_the_start_once_the_cliff_passed() {
+        let schedule = vesting_schedule(10, 100);
+
+        assert_eq!(vested_amount(&schedule, 110), 100);
+        assert_eq!(vested_amount(&schedule, 150), 500);
+        assert_eq!(vested_amount(&schedule, 199), 990);
+    }
+
+    #[test]
+    fn vests_everything_at_the_end() {
+        let schedule = vesting_schedule(10, 100);
+
+        assert_eq!(vested_amount(&schedule, 200), 1000);
+        assert_eq!(vested_amount(&schedule, u64::MAX), 1000);
+    }
+
+    #[test]
+    fn vests_at_once_when_the_cliff_is_the_whole_period() {
+        let schedule = vesting_schedule(100, 100);
+
+        assert_eq!(vested_amount(&schedule, 199), 0);
+        assert_eq!(vested_amount(&schedule, 200), 1000);
+    }
+
+    #[test]
+    fn reports_what_is_left_to_claim() {
+        let mut schedule = vesting_schedule(0, 100);
+        schedule.claimed_amount = 200;
+
+        let status = vesting_status(schedule, 150);
+        assert_eq!(status.vested_amount, 500);
+        assert_eq!(status.unvested_amount, 500);
+        assert_eq!(status.claimable_amount, 300);
+    }
+}
@@ -1,40 +1,206 @@
-use crate::types::{AgentDetails, AgentType};
+use crate::memory::Memory;
+use crate::types::{
+    AccountBalance, AgentDetails, AgentType, Bounty, Comment, PendingCompensation, Poll,
+    Proposals, SaleState, SaleTrade, VestingSchedule,
+};
 use candid::Principal;
+use ic_stable_structures::{StableBTreeMap, StableCell};
+
+// bump whenever the layout of anything stored below changes, and add the
+// matching step to `migrate_state`
+pub const STATE_SCHEMA_VERSION: u32 = 1;
 
 pub struct State {
-    pub agent: AgentDetails,
+    pub agent: StableCell<AgentDetails, Memory>,
+
+    pub proposals: StableBTreeMap<String, Proposals, Memory>,
+
+    pub proposal_count: StableCell<u64, Memory>,
+
+    pub schema_version: StableCell<u32, Memory>,
+
+    pub bounties: StableBTreeMap<String, Bounty, Memory>,
+
+    pub polls: StableBTreeMap<String, Poll, Memory>,
+
+    pub comments: StableBTreeMap<String, Comment, Memory>,
+
+    pub comment_count: StableCell<u64, Memory>,
+
+    pub sale: StableCell<SaleState, Memory>,
+
+    pub sale_trades: StableBTreeMap<u64, SaleTrade, Memory>,
+
+    pub vesting_schedules: StableBTreeMap<Principal, VestingSchedule, Memory>,
+
+    pub stakes: StableBTreeMap<Principal, AccountBalance, Memory>,
+
+    pub compensations: StableBTreeMap<u64, PendingCompensation, Memory>,
 }
 
 impl State {
     pub fn new() -> Self {
         Self {
-            agent : AgentDetails {
-                agent_id: Principal::anonymous(),
-                agent_name: String::new(),
-                agent_category: String::new(),
-                agent_type: AgentType::StandardLaunch,
-                agent_overview: String::new(),
-                members: Vec::new(),
-                agent_website: String::new(),
-                agent_twitter: String::new(),
-                members_count: 0,
-                agent_discord: String::new(),
-                agent_telegram: String::new(),
-                token_name: String::new(),
-                token_symbol: String::new(),
-                image_canister: Principal::anonymous(),
-                token_supply: 0,
-                agent_description: String::new(),
-                image_id: String::new(),
-                agent_lunch_time: 0,
-                token_ledger_id: Principal::anonymous(),
-            },
+            agent: init_agent_details(),
+            proposals: init_proposals(),
+            proposal_count: init_proposal_count(),
+            schema_version: init_schema_version(),
+            bounties: init_bounties(),
+            polls: init_polls(),
+            comments: init_comments(),
+            comment_count: init_comment_count(),
+            sale: init_sale(),
+            sale_trades: init_sale_trades(),
+            vesting_schedules: init_vesting_schedules(),
+            stakes: init_stakes(),
+            compensations: init_compensations(),
         }
     }
+
+    pub fn agent(&self) -> &AgentDetails {
+        self.agent.get()
+    }
+
+    pub fn update_agent<R>(&mut self, f: impl FnOnce(&mut AgentDetails) -> R) -> R {
+        let mut agent = self.agent.get().clone();
+        let res = f(&mut agent);
+        self.agent
+            .set(agent)
+            .expect("failed to save agent details");
+        res
+    }
+
+    pub fn next_proposal_id(&mut self) -> String {
+        let id = self.proposal_count.get() + 1;
+        self.proposal_count
+            .set(id)
+            .expect("failed to save proposal count");
+        id.to_string()
+    }
+
+    pub fn update_sale<R>(&mut self, f: impl FnOnce(&mut SaleState) -> R) -> R {
+        let mut sale = self.sale.get().clone();
+        let res = f(&mut sale);
+        self.sale.set(sale).expect("failed to save sale state");
+        res
+    }
+
+    pub fn next_comment_id(&mut self) -> String {
+        let id = self.comment_count.get() + 1;
+        self.comment_count
+            .set(id)
+            .expect("failed to save comment count");
+        id.to_string()
+    }
+}
+
+fn empty_agent_details() -> AgentDetails {
+    AgentDetails {
+        agent_id: Principal::anonymous(),
+        agent_name: String::new(),
+        agent_category: String::new(),
+        agent_type: AgentType::StandardLaunch,
+        agent_overview: String::new(),
+        members: Vec::new(),
+        agent_website: String::new(),
+        agent_twitter: String::new(),
+        members_count: 0,
+        agent_discord: String::new(),
+        agent_telegram: String::new(),
+        token_name: String::new(),
+        token_symbol: String::new(),
+        image_canister: Principal::anonymous(),
+        token_supply: 0,
+        agent_description: String::new(),
+        image_id: String::new(),
+        agent_lunch_time: 0,
+        token_ledger_id: Principal::anonymous(),
+        proposal_entry: Vec::new(),
+        member_groups: Vec::new(),
+        neuropad_backend_id: Principal::anonymous(),
+    }
+}
+
+fn init_agent_details() -> StableCell<AgentDetails, Memory> {
+    StableCell::init(crate::memory::get_agent_memory(), empty_agent_details())
+        .expect("failed to init agent details")
+}
+
+fn init_proposals() -> StableBTreeMap<String, Proposals, Memory> {
+    StableBTreeMap::init(crate::memory::get_proposal_memory())
+}
+
+fn init_bounties() -> StableBTreeMap<String, Bounty, Memory> {
+    StableBTreeMap::init(crate::memory::get_bounty_memory())
+}
+
+fn init_polls() -> StableBTreeMap<String, Poll, Memory> {
+    StableBTreeMap::init(crate::memory::get_poll_memory())
+}
+
+fn init_comments() -> StableBTreeMap<String, Comment, Memory> {
+    StableBTreeMap::init(crate::memory::get_comment_memory())
+}
+
+fn init_comment_count() -> StableCell<u64, Memory> {
+    StableCell::init(crate::memory::get_comment_count_memory(), 0)
+        .expect("failed to init comment count")
+}
+
+fn init_sale() -> StableCell<SaleState, Memory> {
+    StableCell::init(crate::memory::get_sale_state_memory(), SaleState::default())
+        .expect("failed to init sale state")
+}
+
+fn init_sale_trades() -> StableBTreeMap<u64, SaleTrade, Memory> {
+    StableBTreeMap::init(crate::memory::get_sale_trade_memory())
+}
+
+fn init_vesting_schedules() -> StableBTreeMap<Principal, VestingSchedule, Memory> {
+    StableBTreeMap::init(crate::memory::get_vesting_memory())
+}
+
+fn init_stakes() -> StableBTreeMap<Principal, AccountBalance, Memory> {
+    StableBTreeMap::init(crate::memory::get_stake_memory())
+}
+
+fn init_compensations() -> StableBTreeMap<u64, PendingCompensation, Memory> {
+    StableBTreeMap::init(crate::memory::get_compensation_memory())
+}
+
+fn init_proposal_count() -> StableCell<u64, Memory> {
+    StableCell::init(crate::memory::get_proposal_count_memory(), 0)
+        .expect("failed to init proposal count")
+}
+
+fn init_schema_version() -> StableCell<u32, Memory> {
+    StableCell::init(
+        crate::memory::get_schema_version_memory(),
+        STATE_SCHEMA_VERSION,
+    )
+    .expect("failed to init schema version")
+}
+
+// brings data written by an older release up to `STATE_SCHEMA_VERSION`
+pub fn migrate_state(state: &mut State) {
+    let stored_version = *state.schema_version.get();
+
+    if stored_version > STATE_SCHEMA_VERSION {
+        ic_cdk::trap(&format!(
+            "stable state schema {} is newer than supported schema {}",
+            stored_version, STATE_SCHEMA_VERSION
+        ));
+    }
+
+    // version 1 is the first stable layout, so there is nothing to convert yet
+    state
+        .schema_version
+        .set(STATE_SCHEMA_VERSION)
+        .expect("failed to save schema version");
 }
 
 impl Default for State {
     fn default() -> Self {
         State::new()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,95 @@
+// builders shared by the unit tests of the controllers
+use crate::types::{
+    AccountBalance, Poll, PollOptions, ProposalState, ProposalType, Proposals, SaleConfig,
+    VestingSchedule,
+};
+use candid::Principal;
+
+pub fn principal(id: u8) -> Principal {
+    Principal::from_slice(&[id])
+}
+
+// an open proposal expiring at 100, with a 50% quorum and a 51% approval threshold
+pub fn proposal(total_voting_power: u64, approved_votes: u64, rejected_votes: u64) -> Proposals {
+    Proposals {
+        proposal_id: String::from("1"),
+        proposal_title: String::new(),
+        proposal_description: String::new(),
+        proposal_status: ProposalState::Open,
+        proposal_submitted_at: 0,
+        proposal_expired_at: 100,
+        proposal_approved_votes: approved_votes,
+        approved_votes_list: Vec::new(),
+        proposal_rejected_votes: rejected_votes,
+        rejected_votes_list: Vec::new(),
+        quorum_percentage: 50,
+        approval_percentage: 51,
+        total_voting_power,
+        voting_power: Vec::new(),
+        created_by: Principal::anonymous(),
+        proposal_type: ProposalType::GeneralPurpose,
+        proposal_entry: String::new(),
+        executed_block_index: None,
+        execution_error: None,
+        execution_created_at: None,
+    }
+}
+
+// a head-counted poll expiring at 100 with one option per entry of `votes`
+pub fn poll(votes: &[u64]) -> Poll {
+    Poll {
+        poll_id: String::from("1"),
+        poll_query: String::new(),
+        description: String::new(),
+        poll_options: votes
+            .iter()
+            .enumerate()
+            .map(|(index, votes)| PollOptions {
+                option: format!("option {}", index),
+                id: index.to_string(),
+                poll_approved_votes: *votes,
+                approved_users: Vec::new(),
+            })
+            .collect(),
+        proposal_entry: String::new(),
+        token_weighted: false,
+        voting_power: Vec::new(),
+        created_by: Principal::anonymous(),
+        created_at: 0,
+        proposal_expired_at: 100,
+    }
+}
+
+// an open sale of 1000 tokens on the curve of `base_price` and `slope`
+pub fn sale_config(base_price: u64, slope: u64) -> SaleConfig {
+    SaleConfig {
+        payment_ledger_id: Principal::management_canister(),
+        base_price,
+        slope,
+        sale_supply: 1000,
+        is_open: true,
+    }
+}
+
+// 1000 tokens vesting from 100 over `vesting_duration` seconds
+pub fn vesting_schedule(cliff_duration: u64, vesting_duration: u64) -> VestingSchedule {
+    VestingSchedule {
+        beneficiary: Principal::anonymous(),
+        total_amount: 1000,
+        claimed_amount: 0,
+        start_at: 100,
+        cliff_duration,
+        vesting_duration,
+        subaccount: Vec::new(),
+    }
+}
+
+pub fn stake(staked: u64, lock_duration: u64, locked_until: u64) -> AccountBalance {
+    AccountBalance {
+        id: Principal::anonymous(),
+        staked,
+        lock_duration,
+        locked_until,
+        updated_at: 0,
+    }
+}
@@ -1,7 +1,9 @@
-use candid::{CandidType, Decode, Encode, Principal};
+use candid::{CandidType, Decode, Encode, Nat, Principal};
+use ic_stable_structures::{storable::Bound, Storable};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
-#[derive(Clone, Debug, CandidType, Deserialize, PartialEq)]
+#[derive(Clone, Debug, CandidType, Serialize, Deserialize, PartialEq)]
 pub enum ProposalState {
     Open,
     Accepted,
@@ -23,31 +25,142 @@ pub enum TransferResult {
 )]
 pub struct ProposalPlace {
     pub place_name : String,
+    // minimum voting power a member needs to submit a proposal here
     pub min_required_thredshold : u64,
+    // share of the snapshot voting power that has to take part in the vote
+    pub quorum_percentage : u64,
+    // share of the cast voting power that has to vote Yes
+    pub approval_percentage : u64,
 }
 
 
-#[derive(Clone, CandidType, Deserialize, Serialize)]
+// tokens a principal keeps in its staking subaccount, `locked_until` is in nanoseconds
+// and the lock boosts voting power until it runs out
+#[derive(Clone, CandidType, Deserialize, Serialize, Debug)]
 pub struct AccountBalance {
     pub id: Principal,
-    pub staked: u32,
+    pub staked: u64,
+    pub lock_duration: u64,
+    pub locked_until: u64,
+    pub updated_at: u64,
+}
+
+#[derive(Clone, CandidType, Deserialize, Serialize, Debug)]
+pub struct StakeArgs {
+    pub amount: u64,
+    // locks the whole stake, a lock can only be extended
+    pub lock_seconds: Option<u64>,
+}
+
+#[derive(Clone, CandidType, Deserialize, Serialize, Debug)]
+pub struct StakeStatus {
+    pub balance: AccountBalance,
+    pub voting_power: u64,
 }
 
-#[derive(Clone, CandidType, Deserialize, Serialize)]
+#[derive(Clone, CandidType, Deserialize, Serialize, Debug)]
 pub struct MintTokenArgs {
     pub total_amount: u64,
     pub description: String,
     pub proposal_entry : String,
 }
 
+// linear bonding curve, prices are in NeuroPad base units per `CURVE_PRICE_SCALE` agent
+// base units and rise by `slope` for every `CURVE_PRICE_SCALE` agent base units sold
+#[derive(Clone, CandidType, Deserialize, Serialize, Debug)]
+pub struct SaleConfig {
+    pub payment_ledger_id: Principal,
+    pub base_price: u64,
+    pub slope: u64,
+    // most agent tokens the treasury sells through the curve
+    pub sale_supply: u64,
+    pub is_open: bool,
+}
+
+#[derive(Clone, CandidType, Deserialize, Serialize, Debug)]
+pub struct ConfigureSaleArgs {
+    pub config: SaleConfig,
+    pub description: String,
+    pub proposal_entry: String,
+}
+
+#[derive(Clone, CandidType, Deserialize, Serialize, Debug, Default)]
+pub struct SaleState {
+    pub config: Option<SaleConfig>,
+    // position on the curve, tokens bought minus tokens sold back
+    pub tokens_sold: u64,
+    // NeuroPad tokens held on the sale reserve subaccount to pay out sells
+    pub reserve_balance: u64,
+    // agent tokens moved from the treasury to the sale supply subaccount so far,
+    // the subaccount holds this minus `tokens_sold`
+    pub funded_supply: Option<u64>,
+}
+
+#[derive(Clone, CandidType, Deserialize, Serialize, Debug, PartialEq)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+#[derive(Clone, CandidType, Deserialize, Serialize, Debug)]
+pub struct SaleTrade {
+    pub trade_id: u64,
+    pub trader: Principal,
+    pub side: TradeSide,
+    pub token_amount: u64,
+    pub payment_amount: u64,
+    pub tokens_sold_after: u64,
+    pub token_block_index: Nat,
+    pub payment_block_index: Nat,
+    pub traded_at: u64,
+}
+
+// what a failed trade still owes its trader once every compensation attempt failed,
+// `created_at_time` is kept while the ledger may hold the last attempt
+#[derive(Clone, CandidType, Deserialize, Serialize, Debug)]
+pub struct PendingCompensation {
+    pub compensation_id: u64,
+    pub trader: Principal,
+    pub side: TradeSide,
+    pub ledger_id: Principal,
+    pub amount: u64,
+    pub created_at_time: Option<u64>,
+    pub error: String,
+    pub recorded_at: u64,
+    pub retrying: bool,
+}
+
+// `max_cost` and `min_return` protect the trader from the price moving before the trade runs
+#[derive(Clone, CandidType, Deserialize, Serialize, Debug)]
+pub struct BuyTokensArgs {
+    pub token_amount: u64,
+    pub max_cost: u64,
+}
+
+#[derive(Clone, CandidType, Deserialize, Serialize, Debug)]
+pub struct SellTokensArgs {
+    pub token_amount: u64,
+    pub min_return: u64,
+}
+
+#[derive(Clone, CandidType, Deserialize, Serialize, Debug)]
+pub struct SaleQuote {
+    pub token_amount: u64,
+    pub payment_amount: u64,
+    pub tokens_sold_after: u64,
+}
+
 
 #[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
 pub struct Comment {
     pub author_principal: Principal,
     pub comment_text: String,
     pub comment_id: String,
+    pub proposal_id: String,
     pub replies: Vec<ReplayComment>,
     pub likes: u16,
+    // principals that liked the comment, a principal is counted once
+    pub liked_by: Vec<Principal>,
     pub created_at: u64,
 }
 
@@ -55,16 +168,42 @@ pub struct Comment {
 pub struct ReplayComment {
     pub reply_comment : String,
     pub commented_by : Principal,
+    pub created_at: u64,
 }
 
 #[derive(Clone , Debug, CandidType, Serialize, Deserialize)]
 pub struct PollOptions {
     pub option : String,
     pub id: String,
+    // votes in heads, or in snapshot voting power for token weighted polls
     pub poll_approved_votes : u64,
     pub approved_users : Vec<Principal>,
 }
 
+#[derive(Clone, Debug, CandidType, Serialize, Deserialize)]
+pub struct Poll {
+    pub poll_id: String,
+    pub poll_query: String,
+    pub description: String,
+    pub poll_options: Vec<PollOptions>,
+    pub proposal_entry: String,
+    pub token_weighted: bool,
+    // members allowed to vote and their staked voting power when the poll was created
+    pub voting_power: Vec<(Principal, u64)>,
+    pub created_by: Principal,
+    pub created_at: u64,
+    pub proposal_expired_at: u64,
+}
+
+#[derive(Clone, Debug, CandidType, Serialize, Deserialize)]
+pub struct PollResult {
+    pub poll: Poll,
+    pub is_open: bool,
+    pub total_votes: u64,
+    // none while nobody voted or when the leading options are tied
+    pub winning_option: Option<PollOptions>,
+}
+
 #[derive(CandidType, Serialize, Deserialize)]
 pub struct Pagination {
     pub start: u32,
@@ -116,6 +255,15 @@ pub struct AgentDetails {
     pub image_id: String,
     pub agent_lunch_time : u64,
     pub token_ledger_id: Principal, 
+    pub proposal_entry: Vec<ProposalPlace>,
+    pub member_groups: Vec<MemberGroup>,
+    pub neuropad_backend_id: Principal,
+}
+
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
+pub struct MemberGroup {
+    pub group_name: String,
+    pub group_members: Vec<Principal>,
 }
 
 #[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
@@ -137,6 +285,38 @@ pub struct AgentCreationInput {
     pub agent_description: String,
     pub image_id: String,
     pub agent_lunch_time : u64,
+    pub proposal_entry: Option<Vec<ProposalPlace>>,
+    pub vesting: Option<Vec<VestingAllocation>>,
+}
+
+// set at launch, the ledger mints `amount` to the vesting subaccount of the beneficiary
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
+pub struct VestingAllocation {
+    pub beneficiary: Principal,
+    pub amount: u64,
+    pub cliff_seconds: u64,
+    pub vesting_seconds: u64,
+}
+
+// times are in nanoseconds, tokens vest linearly from `start_at` and none
+// can be claimed before the cliff has passed
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
+pub struct VestingSchedule {
+    pub beneficiary: Principal,
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub start_at: u64,
+    pub cliff_duration: u64,
+    pub vesting_duration: u64,
+    pub subaccount: Vec<u8>,
+}
+
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
+pub struct VestingStatus {
+    pub schedule: VestingSchedule,
+    pub vested_amount: u64,
+    pub unvested_amount: u64,
+    pub claimable_amount: u64,
 }
 
 #[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
@@ -178,6 +358,32 @@ pub struct BountyDone{
     pub associated_proposal_id : String,
 }
 
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug, PartialEq)]
+pub enum BountyStatus {
+    Open,
+    Claimed,
+    Completed,
+    Refunded,
+}
+
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
+pub struct Bounty {
+    // id of the BountyRaised proposal that funded the bounty
+    pub bounty_id: String,
+    pub bounty_task: String,
+    pub description: String,
+    pub tokens: u64,
+    pub escrow_subaccount: Vec<u8>,
+    pub escrow_block_index: Nat,
+    pub bounty_status: BountyStatus,
+    pub claimed_by: Option<Principal>,
+    pub claimed_at: Option<u64>,
+    pub created_at: u64,
+    pub bounty_expired_at: u64,
+    // block of the payout to the claimant or of the refund to the treasury
+    pub settled_block_index: Option<Nat>,
+}
+
 #[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
 pub struct CreatePoll{
     pub description: String,
@@ -185,6 +391,7 @@ pub struct CreatePoll{
     pub proposal_expired_at: u64,
     pub proposal_entry : String,
     pub poll_query : String,
+    pub token_weighted: bool,
 }
 
 #[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
@@ -200,6 +407,76 @@ pub enum VoteParam {
     No,
 }
 
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
+pub enum ProposalType {
+    GeneralPurpose,
+    TokenTransfer(TokenTransferPolicy),
+    AddMember(AddMemberArgs),
+    RemoveMember(RemoveMemberArgs),
+    BountyRaised(BountyRaised),
+    BountyDone(BountyDone),
+    MintToken(MintTokenArgs),
+    ConfigureSale(SaleConfig),
+}
+
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
+pub struct ProposalInput {
+    pub proposal_title: String,
+    pub proposal_description: String,
+    pub proposal_type: ProposalType,
+    pub proposal_entry: String,
+    pub proposal_expired_at: Option<u64>,
+}
+
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
+pub struct Proposals {
+    pub proposal_id: String,
+    pub proposal_title: String,
+    pub proposal_description: String,
+    pub proposal_status: ProposalState,
+    pub proposal_submitted_at: u64,
+    pub proposal_expired_at: u64,
+    pub proposal_approved_votes: u64,
+    pub approved_votes_list: Vec<Principal>,
+    pub proposal_rejected_votes: u64,
+    pub rejected_votes_list: Vec<Principal>,
+    pub quorum_percentage: u64,
+    pub approval_percentage: u64,
+    pub total_voting_power: u64,
+    pub voting_power: Vec<(Principal, u64)>,
+    pub created_by: Principal,
+    pub proposal_type: ProposalType,
+    pub proposal_entry: String,
+    // ledger block of the transfer made when the proposal was executed
+    pub executed_block_index: Option<Nat>,
+    // reason of the last failed execution, the proposal stays retriable
+    pub execution_error: Option<String>,
+    // set on the first execution and reused as the ledger created_at_time on every retry
+    pub execution_created_at: Option<u64>,
+}
+
+// proposal summary kept in the NeuroPad backend index of all agent proposals
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
+pub struct ProposalValueStore {
+    pub associated_agent_canister_id: Principal,
+    pub proposal_id: String,
+    pub propsal_title: String,
+    pub proposal_description: String,
+    pub proposal_status: Option<ProposalState>,
+    pub proposal_submitted_at: u64,
+    pub proposal_expired_at: u64,
+    pub created_by: Principal,
+    pub proposal_entry: Option<String>,
+}
+
+// mint request sent to the NeuroPad backend, which is the minting account of the agent ledger
+#[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
+pub struct MintRequest {
+    pub proposal_id: String,
+    pub amount: Nat,
+    pub created_at_time: Option<u64>,
+}
+
 #[derive(Clone, CandidType, Serialize, Deserialize, Debug)]
 pub struct TokenTransferArgs {
     pub tokens: u64,
@@ -207,8 +484,123 @@ pub struct TokenTransferArgs {
     pub to: Principal,
 }
 
-#[derive(CandidType, Serialize, Deserialize)]
-pub struct TokenBalanceArgs {
-    pub owner: Principal,
-    pub subaccount: Option<Vec<u8>>,
-}
\ No newline at end of file
+
+impl Storable for AgentDetails {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for Proposals {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for Bounty {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for AccountBalance {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for VestingSchedule {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for SaleState {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for PendingCompensation {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for SaleTrade {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for Poll {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for Comment {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
@@ -1 +1,40 @@
-pub const WARNING_ANONYMOUS_CALL: &str = "Anonymous principal not allowed !";
\ No newline at end of file
+pub const WARNING_ANONYMOUS_CALL: &str = "Anonymous principal not allowed !";
+pub const NOT_A_CONTROLLER: &str = "Only controllers of the agent canister are allowed !";
+pub const NOT_A_MEMBER: &str = "Only agent members are allowed to perform this action !";
+pub const PROPOSAL_NOT_FOUND: &str = "Proposal not found !";
+pub const PROPOSAL_NOT_OPEN: &str = "Proposal is no longer open for voting !";
+pub const ALREADY_VOTED: &str = "You have already voted on this proposal !";
+pub const PROPOSAL_PLACE_NOT_FOUND: &str = "Proposal entry not found for this agent !";
+pub const PROPOSAL_NOT_ACCEPTED: &str = "Only accepted or unreachable proposals can be executed !";
+pub const NO_VOTING_POWER: &str = "No voting power, staked agent tokens are required to vote !";
+pub const INSUFFICIENT_VOTING_POWER: &str = "Not enough staked agent tokens to create a proposal here.";
+pub const GROUP_NOT_FOUND: &str = "Member group not found !";
+pub const BOUNTY_NOT_FOUND: &str = "Bounty not found !";
+pub const POLL_NOT_FOUND: &str = "Poll not found !";
+pub const POLL_OPTION_NOT_FOUND: &str = "Poll option not found !";
+pub const POLL_CLOSED: &str = "Poll is closed !";
+pub const COMMENT_NOT_FOUND: &str = "Comment not found !";
+pub const LEDGER_NOT_SET: &str = "Agent token ledger is not set yet !";
+pub const SALE_NOT_OPEN: &str = "Token sale of this agent is not open !";
+pub const VESTING_NOT_FOUND: &str = "No vesting schedule found for this principal !";
+pub const STAKE_NOT_FOUND: &str = "No staked tokens found for this principal !";
+pub const COMPENSATION_NOT_FOUND: &str = "Compensation not found !";
+pub const EXECUTION_INTERRUPTED: &str = "Execution was interrupted by an upgrade, run the proposal again.";
+
+pub const DEFAULT_PROPOSAL_PLACE: &str = "General";
+pub const DEFAULT_MEMBER_GROUP: &str = "Council";
+pub const MAX_POLL_OPTIONS: usize = 10;
+pub const MAX_COMMENT_LENGTH: usize = 2000;
+pub const DEFAULT_QUORUM_PERCENTAGE: u64 = 50;
+pub const DEFAULT_APPROVAL_PERCENTAGE: u64 = 51;
+// default voting period of a proposal, 7 days in nanoseconds
+pub const PROPOSAL_DURATION: u64 = 7 * 24 * 60 * 60 * 1_000_000_000;
+// a bounty that is not done within 30 days can be refunded to the treasury
+pub const BOUNTY_DURATION: u64 = 30 * 24 * 60 * 60 * 1_000_000_000;
+// agent base units the curve prices are quoted for
+pub const CURVE_PRICE_SCALE: u128 = 100_000_000;
+// tries of a transfer that hands back what a failed trade already took
+pub const COMPENSATION_ATTEMPTS: u32 = 2;
+// a lock of MAX_LOCK_DURATION doubles voting power, shorter locks boost it linearly
+pub const MAX_LOCK_DURATION: u64 = 365 * 24 * 60 * 60 * 1_000_000_000;
+pub const MAX_LOCK_BOOST_PERCENTAGE: u64 = 100;
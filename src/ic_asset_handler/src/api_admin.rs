@@ -1,7 +1,7 @@
 use candid::Principal;
 use std::collections::BTreeSet;
 
-use crate::{is_controller, store, ANONYMOUS};
+use crate::{is_controller, store, types::UpdateBucketInput, ANONYMOUS};
 
 #[ic_cdk::update(guard = "is_controller")]
 fn admin_set_managers(args: BTreeSet<Principal>) -> Result<(), String> {
@@ -40,3 +40,32 @@ fn validate_admin_set_auditors(args: BTreeSet<Principal>) -> Result<(), String>
     }
     Ok(())
 }
+
+// visibility, the keys trusted to sign access tokens and the per owner quotas
+#[ic_cdk::update(guard = "is_controller")]
+fn admin_update_bucket(args: UpdateBucketInput) -> Result<(), String> {
+    args.validate()?;
+    store::state::with_mut(|r| {
+        if let Some(visibility) = args.visibility {
+            r.visibility = visibility;
+        }
+        if let Some(keys) = args.trusted_ecdsa_pub_keys {
+            r.trusted_ecdsa_pub_keys = keys;
+        }
+        if let Some(keys) = args.trusted_eddsa_pub_keys {
+            r.trusted_eddsa_pub_keys = keys;
+        }
+        if let Some(max_files) = args.max_files_per_owner {
+            r.max_files_per_owner = max_files;
+        }
+        if let Some(max_storage) = args.max_storage_per_owner {
+            r.max_storage_per_owner = max_storage;
+        }
+    });
+    Ok(())
+}
+
+#[ic_cdk::update]
+fn validate_admin_update_bucket(args: UpdateBucketInput) -> Result<(), String> {
+    args.validate()
+}
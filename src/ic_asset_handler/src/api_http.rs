@@ -11,7 +11,7 @@ use std::path::Path;
 use std::str::FromStr;
 
 use crate::types::UrlFileParam;
-use crate::{to_cbor_bytes, MAX_CHUNK_SIZE, MAX_FILE_SIZE_PER_CALL};
+use crate::{check_http_access, to_cbor_bytes, MAX_CHUNK_SIZE, MAX_FILE_SIZE_PER_CALL};
 
 use crate::store;
 
@@ -81,7 +81,8 @@ static IC_CERTIFICATE_EXPRESSION_HEADER: &str = "ic-certificateexpression";
 // https://mmrxu-fqaaa-aaaap-ahhna-cai.icp0.io/f/1
 // http://mmrxu-fqaaa-aaaap-ahhna-cai.localhost:4943/f/1 // download file by id 1
 // http://mmrxu-fqaaa-aaaap-ahhna-cai.localhost:4943/h/8546ffa4296a6960e9e64e95de178d40c231a0cd358a65477bc56a105dda1c1d //download file by hash 854...
-// TODO: 1. support range request; 2. token verification; 3. cache control
+// private files need an access token with read scope in the url, `/f/1?token=<base64url>`
+// TODO: 1. support range request; 2. cache control
 #[ic_cdk::query]
 fn http_request(request: HttpRequest) -> HttpStreamingResponse {
     let witness = store::state::http_tree_with(|t| {
@@ -131,6 +132,17 @@ fn http_request(request: HttpRequest) -> HttpStreamingResponse {
                     ..Default::default()
                 },
                 Some(metadata) => {
+                    let access =
+                        check_http_access(param.token.as_ref(), metadata.parent);
+                    if let Err(err) = access {
+                        return HttpStreamingResponse {
+                            status_code: 403,
+                            headers,
+                            body: ByteBuf::from(err.as_bytes()),
+                            ..Default::default()
+                        };
+                    }
+
                     if metadata.size != metadata.filled {
                         return HttpStreamingResponse {
                             status_code: 422,
@@ -198,6 +210,7 @@ fn http_request(request: HttpRequest) -> HttpStreamingResponse {
                         )
                     };
 
+                    let token_used = param.token.is_some();
                     let streaming_strategy = create_strategy(StreamingCallbackToken {
                         id,
                         chunk_index,
@@ -208,10 +221,13 @@ fn http_request(request: HttpRequest) -> HttpStreamingResponse {
                     // small file
                     if streaming_strategy.is_none() {
                         headers.push(("content-length".to_string(), body.len().to_string()));
-                        headers.push((
-                            "cache-control".to_string(),
-                            "max-age=2592000, public".to_string(),
-                        ));
+                        // files read with a token must not end up in shared caches
+                        let cache_control = if token_used {
+                            "max-age=2592000, private"
+                        } else {
+                            "max-age=2592000, public"
+                        };
+                        headers.push(("cache-control".to_string(), cache_control.to_string()));
                     }
 
                     HttpStreamingResponse {
@@ -229,6 +245,13 @@ fn http_request(request: HttpRequest) -> HttpStreamingResponse {
 
 #[ic_cdk::query]
 fn http_request_streaming_callback(token: StreamingCallbackToken) -> StreamingCallbackHttpResponse {
+    let parent = store::fs::get_file(token.id)
+        .map(|metadata| metadata.parent)
+        .unwrap_or_else(|| ic_cdk::trap("file not found"));
+    if let Err(err) = check_http_access(token.token.as_ref(), parent) {
+        ic_cdk::trap(&err);
+    }
+
     match store::fs::get_chunk(token.id, token.chunk_index) {
         None => ic_cdk::trap("chunk not found"),
         Some(chunk) => StreamingCallbackHttpResponse {
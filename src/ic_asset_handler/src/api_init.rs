@@ -1,6 +1,6 @@
 
 use crate::store;
-use crate::MAX_FILE_SIZE;
+use crate::{DEFAULT_MAX_FILES_PER_OWNER, DEFAULT_MAX_STORAGE_PER_OWNER, MAX_FILE_SIZE};
 
 #[ic_cdk::init]
 fn init() {
@@ -9,6 +9,10 @@ fn init() {
         b.max_file_size = MAX_FILE_SIZE;
         b.max_dir_depth = 10;
         b.max_children = 1000;
+        // agent images are served publicly, private buckets need access tokens to read
+        b.visibility = 1;
+        b.max_files_per_owner = DEFAULT_MAX_FILES_PER_OWNER;
+        b.max_storage_per_owner = DEFAULT_MAX_STORAGE_PER_OWNER;
     });
 
     store::state::save();
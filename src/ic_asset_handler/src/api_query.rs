@@ -1,5 +1,6 @@
 use serde_bytes::ByteBuf;
-use crate::{store, types::{FileChunk, FileInfo}};
+use candid::{Nat, Principal};
+use crate::{check_access, cose::Permission, is_controller_or_manager, store, types::{FileChunk, FileInfo, FolderInfo, UsageInfo}};
 
 #[ic_cdk::query]
 fn api_version() -> u16 {
@@ -12,13 +13,45 @@ fn api_version() -> u16 {
 // }
 
 #[ic_cdk::query]
-fn get_file_info(id: u32, _access_token: Option<ByteBuf>) -> Result<FileInfo, String> {
+fn get_file_info(id: u32, access_token: Option<ByteBuf>) -> Result<FileInfo, String> {
     match store::fs::get_file(id) {
-        Some(meta) => Ok(meta.into_info(id)),
+        Some(meta) => {
+            check_access(access_token.as_ref(), Permission::Read, meta.parent)?;
+            Ok(meta.into_info(id))
+        }
         None => Err("file not found".to_string()),
     }
 }
 
+#[ic_cdk::query]
+fn get_file_info_by_path(
+    path: String,
+    access_token: Option<ByteBuf>,
+) -> Result<FileInfo, String> {
+    let id = store::fs::find_file(&path)?;
+    get_file_info(id, access_token)
+}
+
+#[ic_cdk::query]
+fn get_folder_info(id: u32, access_token: Option<ByteBuf>) -> Result<FolderInfo, String> {
+    match store::fs::get_folder(id) {
+        Some(meta) => {
+            check_access(access_token.as_ref(), Permission::Read, id)?;
+            Ok(meta.into_info(id))
+        }
+        None => Err("folder not found".to_string()),
+    }
+}
+
+#[ic_cdk::query]
+fn get_folder_info_by_path(
+    path: String,
+    access_token: Option<ByteBuf>,
+) -> Result<FolderInfo, String> {
+    let id = store::fs::find_folder(&path)?;
+    get_folder_info(id, access_token)
+}
+
 // #[ic_cdk::query]
 // fn get_file_info_by_hash(
 //     hash: ByteBuf,
@@ -42,8 +75,11 @@ fn get_file_chunks(
     id: u32,
     index: u32,
     take: Option<u32>,
-    _access_token: Option<ByteBuf>,
+    access_token: Option<ByteBuf>,
 ) -> Result<Vec<FileChunk>, String> {
+    let meta = store::fs::get_file(id).ok_or("file not found")?;
+    check_access(access_token.as_ref(), Permission::Read, meta.parent)?;
+
     Ok(store::fs::get_chunks(id, index, take.unwrap_or(10).min(8)))
 }
 
@@ -52,10 +88,53 @@ fn list_files(
     parent: u32,
     prev: Option<u32>,
     take: Option<u32>,
-    _access_token: Option<ByteBuf>,
-) -> Vec<FileInfo> {
+    access_token: Option<ByteBuf>,
+) -> Result<Vec<FileInfo>, String> {
+    check_access(access_token.as_ref(), Permission::Read, parent)?;
+
     let max_prev = store::state::with(|s| s.file_id).saturating_add(1);
     let prev = prev.unwrap_or(max_prev).min(max_prev);
     let take = take.unwrap_or(10).min(100);
-    store::fs::list_files(parent, prev, take)
+    Ok(store::fs::list_files(parent, prev, take))
+}
+
+#[ic_cdk::query]
+fn list_folders(
+    parent: u32,
+    prev: Option<u32>,
+    take: Option<u32>,
+    access_token: Option<ByteBuf>,
+) -> Result<Vec<FolderInfo>, String> {
+    check_access(access_token.as_ref(), Permission::Read, parent)?;
+
+    let max_prev = store::state::with(|s| s.folder_id).saturating_add(1);
+    let prev = prev.unwrap_or(max_prev).min(max_prev);
+    let take = take.unwrap_or(10).min(100);
+    Ok(store::fs::list_folders(parent, prev, take))
+}
+
+// usage of the caller, managers, controllers and auditors can look up any owner
+#[ic_cdk::query]
+fn get_usage(owner: Option<Principal>) -> Result<UsageInfo, String> {
+    let caller = ic_cdk::caller();
+    let owner = owner.unwrap_or(caller);
+    if owner != caller
+        && !is_controller_or_manager(&caller)
+        && !store::state::with(|s| s.auditors.contains(&caller))
+    {
+        return Err(
+            "only managers, controllers or auditors can read the usage of others".to_string(),
+        );
+    }
+
+    let usage = store::fs::get_usage(&owner);
+    let (max_files, max_storage) =
+        store::state::with(|s| (s.max_files_per_owner, s.max_storage_per_owner));
+    Ok(UsageInfo {
+        owner,
+        file_count: usage.files,
+        storage_used: Nat::from(usage.size),
+        max_files,
+        max_storage: Nat::from(max_storage),
+    })
 }
@@ -2,29 +2,79 @@ use candid::Nat;
 use serde_bytes::ByteBuf;
 
 
-use crate::{crc32, is_controller_or_manager, store, types::{CreateFileInput, UpdateFileChunkInput, UpdateFileChunkOutput, UpdateFileInput, UpdateFileOutput}, unwrap_trap, MAX_CHUNK_SIZE, MILLISECONDS};
+use crate::{check_access, cose::Permission, crc32, is_controller_or_manager, nat_to_u64, store, types::{CreateFileInput, CreateFolderInput, UpdateFileChunkInput, UpdateFileChunkOutput, UpdateFileInput, UpdateFileOutput}, unwrap_trap, MAX_CHUNK_SIZE, MILLISECONDS, ANONYMOUS};
+
+// only the owner, managers and controllers can change a file, and they need write access to
+// the folder holding it
+fn check_file_access(id: u32, access_token: Option<&ByteBuf>) -> Result<(), String> {
+    let metadata = store::fs::get_file(id).ok_or_else(|| format!("file not found: {}", id))?;
+    let caller = ic_cdk::caller();
+    if metadata.owner != Some(caller) && !is_controller_or_manager(&caller) {
+        return Err("only the owner, managers or controllers can change this file".to_string());
+    }
+    check_access(access_token, Permission::Write, metadata.parent)
+}
 
-// #[ic_cdk::update(guard = "is_controller_or_manager")]
+// only the owner, managers and controllers can delete a folder or move files into it,
+// everybody with write access can use the root folder
+fn check_folder_owner(id: u32) -> Result<(), String> {
+    if id == 0 {
+        return Ok(());
+    }
 
+    let folder = store::fs::get_folder(id).ok_or_else(|| format!("folder not found: {}", id))?;
+    let caller = ic_cdk::caller();
+    if folder.owner != Some(caller) && !is_controller_or_manager(&caller) {
+        return Err("only the owner, managers or controllers can change this folder".to_string());
+    }
+    Ok(())
+}
 
 #[ic_cdk::update]
 fn create_file(
     input: CreateFileInput,
-    _access_token: Option<ByteBuf>,
+    access_token: Option<ByteBuf>,
 ) -> Result<u32, String> {
+    check_access(access_token.as_ref(), Permission::Write, input.parent.unwrap_or_default())?;
+
+    // managers upload on behalf of users, everybody else owns what they upload
+    let caller = ic_cdk::caller();
+    let owner = match input.owner {
+        Some(owner) if is_controller_or_manager(&caller) => owner,
+        Some(owner) if owner != caller => {
+            return Err("only managers can upload on behalf of another owner".to_string())
+        }
+        _ => caller,
+    };
+    if owner == ANONYMOUS {
+        return Err("anonymous user is not allowed".to_string());
+    }
     // use trap to make the update fail.
 
     unwrap_trap(input.validate(), "invalid CreateFileInput");
-    // if input.parent != 0 {
-    //     ic_cdk::trap("parent directory not found");
-    // }
 
-    if let Some(size) = input.size {
-        let max_size = store::state::max_file_size();
-        if size > max_size {
+    let max_size = store::state::max_file_size();
+    if let Some(size) = &input.size {
+        if *size > max_size {
             ic_cdk::trap(&format!("file size exceeds the limit {}", max_size));
         }
     }
+    if input.content.len() as u64 > max_size {
+        ic_cdk::trap(&format!("file size exceeds the limit {}", max_size));
+    }
+
+    // the declared size, or the inline content when it is larger, counts against the quota
+    let size = input
+        .size
+        .as_ref()
+        .map(nat_to_u64)
+        .unwrap_or(0)
+        .max(input.content.len() as u64);
+    let max_storage = store::state::with(|s| s.max_storage_per_owner);
+    let used = store::fs::get_usage(&owner).size;
+    if max_storage > 0 && used.saturating_add(size) > max_storage {
+        return Err(format!("storage quota exceeded: {}", max_storage));
+    }
 
     let now_ms = ic_cdk::api::time() / MILLISECONDS;
     let id = unwrap_trap(
@@ -33,7 +83,8 @@ fn create_file(
             content_type: input.content_type,
             hash: input.hash,
             created_at: now_ms,
-            parent: 0,
+            parent: input.parent.unwrap_or_default(),
+            owner: Some(owner),
             ..Default::default()
         }),
         "failed to add file",
@@ -45,46 +96,49 @@ fn create_file(
     //     // created_at: Nat::from(now_ms),
     // };
 
-    if let content = input.content {
-        if let Some(checksum) = input.crc32 {
-            if crc32(&content) != checksum {
-                ic_cdk::trap("crc32 checksum mismatch");
-            }
+    let content = input.content;
+    if let Some(checksum) = input.crc32 {
+        if crc32(&content) != checksum {
+            ic_cdk::trap("crc32 checksum mismatch");
         }
+    }
 
-        for (i, chunk) in content.chunks(MAX_CHUNK_SIZE as usize).enumerate() {
-            let _ = unwrap_trap(
-                store::fs::update_chunk(id, i as u32, now_ms, chunk.to_vec()),
-                "failed to update chunk",
-            );
-        }
+    for (i, chunk) in content.chunks(MAX_CHUNK_SIZE as usize).enumerate() {
+        let _ = unwrap_trap(
+            store::fs::update_chunk(id, i as u32, now_ms, chunk.to_vec()),
+            "failed to update chunk",
+        );
+    }
 
-        if let Some(status) = input.status {
-            unwrap_trap(
-                store::fs::update_file(id, |metadata| {
-                    metadata.status = status;
-                }),
-                "update file failed",
-            );
-        }
+    if let Some(status) = input.status {
+        unwrap_trap(
+            store::fs::update_file(id, |metadata| {
+                metadata.status = status;
+            }),
+            "update file failed",
+        );
     }
 Ok(id)
 
 }
 
-// #[ic_cdk::update(guard = "is_controller_or_manager")]
 #[ic_cdk::update]
 fn update_file_info(
     input: UpdateFileInput,
-    _access_token: Option<ByteBuf>,
+    access_token: Option<ByteBuf>,
 ) -> Result<UpdateFileOutput, String> {
+    check_file_access(input.id, access_token.as_ref())?;
+    if let Some(parent) = input.parent {
+        check_access(access_token.as_ref(), Permission::Write, parent)?;
+        check_folder_owner(parent)?;
+    }
     unwrap_trap(input.validate(), "invalid UpdateFileInput");
 
-    if let Some(_parent) = input.parent {
-        ic_cdk::trap("parent directory not found");
+    let now_ms = ic_cdk::api::time() / MILLISECONDS;
+    if let Some(parent) = input.parent {
+        unwrap_trap(store::fs::move_file(input.id, parent, now_ms), "move file failed");
     }
 
-    let now_ms = ic_cdk::api::time() / MILLISECONDS;
     unwrap_trap(
         store::fs::update_file(input.id, |metadata| {
             if let Some(name) = input.name {
@@ -108,11 +162,13 @@ fn update_file_info(
     })
 }
 
-#[ic_cdk::update(guard = "is_controller_or_manager")]
+#[ic_cdk::update]
 fn update_file_chunk(
     input: UpdateFileChunkInput,
-    _access_token: Option<ByteBuf>,
+    access_token: Option<ByteBuf>,
 ) -> Result<UpdateFileChunkOutput, String> {
+    check_file_access(input.id, access_token.as_ref())?;
+
     let now_ms = ic_cdk::api::time() / MILLISECONDS;
     if let Some(checksum) = input.crc32 {
         if crc32(&input.content) != checksum {
@@ -136,8 +192,61 @@ fn update_file_chunk(
     })
 }
 
-// #[ic_cdk::update(guard = "is_controller_or_manager")]
 #[ic_cdk::update]
-fn delete_file(id: u32, _access_token: Option<ByteBuf>) -> Result<(), String> {
+fn delete_file(id: u32, access_token: Option<ByteBuf>) -> Result<(), String> {
+    check_file_access(id, access_token.as_ref())?;
     store::fs::delete_file(id).map_err(|err| ic_cdk::trap(&err))
 }
+
+#[ic_cdk::update]
+fn create_folder(
+    input: CreateFolderInput,
+    access_token: Option<ByteBuf>,
+) -> Result<u32, String> {
+    check_access(access_token.as_ref(), Permission::Write, input.parent)?;
+    unwrap_trap(input.validate(), "invalid CreateFolderInput");
+
+    let caller = ic_cdk::caller();
+    if caller == ANONYMOUS {
+        return Err("anonymous user is not allowed".to_string());
+    }
+
+    let now_ms = ic_cdk::api::time() / MILLISECONDS;
+    store::fs::add_folder(input.parent, input.name, caller, now_ms)
+        .map_err(|err| ic_cdk::trap(&err))
+}
+
+#[ic_cdk::update]
+fn move_file(
+    id: u32,
+    parent: u32,
+    access_token: Option<ByteBuf>,
+) -> Result<UpdateFileOutput, String> {
+    check_file_access(id, access_token.as_ref())?;
+    check_access(access_token.as_ref(), Permission::Write, parent)?;
+    check_folder_owner(parent)?;
+
+    let now_ms = ic_cdk::api::time() / MILLISECONDS;
+    unwrap_trap(store::fs::move_file(id, parent, now_ms), "move file failed");
+
+    Ok(UpdateFileOutput {
+        updated_at: Nat::from(now_ms),
+    })
+}
+
+// without `recursive` only an empty folder can be deleted
+#[ic_cdk::update]
+fn delete_folder(
+    id: u32,
+    recursive: bool,
+    access_token: Option<ByteBuf>,
+) -> Result<(), String> {
+    let folder = store::fs::get_folder(id).ok_or_else(|| format!("folder not found: {}", id))?;
+    check_access(access_token.as_ref(), Permission::Write, folder.parent)?;
+    check_folder_owner(id)?;
+
+    // users can only delete folders that hold nothing but their own files
+    let caller = ic_cdk::caller();
+    let owner = (!is_controller_or_manager(&caller)).then_some(caller);
+    store::fs::delete_folder(id, recursive, owner).map_err(|err| ic_cdk::trap(&err))
+}
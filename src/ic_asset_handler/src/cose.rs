@@ -0,0 +1,144 @@
+use candid::Principal;
+use coset::{
+    cbor::value::Value,
+    cwt::{ClaimName, ClaimsSet, Timestamp},
+    iana, Algorithm, CborSerializable, CoseSign1,
+};
+use serde_bytes::ByteBuf;
+
+use crate::format_error;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Permission {
+    Read,
+    Write,
+}
+
+// access token, a COSE_Sign1 over CWT claims signed with Ed25519 (EdDSA) or secp256k1 (ES256K).
+// scope is a space separated list of `read`, `write`, `read:<folder>` and `write:<folder>`,
+// a folder entry also covers everything below that folder and write implies read
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub subject: Principal,
+    pub audience: Principal,
+    pub scope: String,
+    pub expiration: i64, // unix timestamp in seconds
+}
+
+impl Token {
+    pub fn from_sign1(
+        data: &[u8],
+        ecdsa_pub_keys: &[ByteBuf],
+        eddsa_pub_keys: &[ByteBuf],
+        now_sec: i64,
+    ) -> Result<Self, String> {
+        let sign1 = CoseSign1::from_slice(data).map_err(format_error)?;
+        match sign1.protected.header.alg {
+            Some(Algorithm::Assigned(iana::Algorithm::EdDSA)) => {
+                sign1.verify_signature(b"", |sig, msg| ed25519_verify(eddsa_pub_keys, sig, msg))?
+            }
+            Some(Algorithm::Assigned(iana::Algorithm::ES256K)) => {
+                sign1.verify_signature(b"", |sig, msg| secp256k1_verify(ecdsa_pub_keys, sig, msg))?
+            }
+            _ => return Err("unsupported token algorithm".to_string()),
+        }
+
+        let payload = sign1.payload.as_deref().unwrap_or_default();
+        let claims = ClaimsSet::from_slice(payload).map_err(format_error)?;
+        Self::from_claims(claims, now_sec)
+    }
+
+    fn from_claims(claims: ClaimsSet, now_sec: i64) -> Result<Self, String> {
+        let expiration = match claims.expiration_time {
+            Some(Timestamp::WholeSeconds(exp)) => exp,
+            Some(Timestamp::FractionalSeconds(exp)) => exp as i64,
+            None => return Err("token expiration is missing".to_string()),
+        };
+        if expiration <= now_sec {
+            return Err("token expired".to_string());
+        }
+
+        match claims.not_before {
+            Some(Timestamp::WholeSeconds(nbf)) if nbf > now_sec => {
+                return Err("token is not valid yet".to_string())
+            }
+            Some(Timestamp::FractionalSeconds(nbf)) if nbf as i64 > now_sec => {
+                return Err("token is not valid yet".to_string())
+            }
+            _ => {}
+        }
+
+        let subject = claims.subject.ok_or("token subject is missing")?;
+        let audience = claims.audience.ok_or("token audience is missing")?;
+        let scope = claims
+            .rest
+            .into_iter()
+            .find_map(|(name, value)| match (name, value) {
+                (ClaimName::Assigned(iana::CwtClaimName::Scope), Value::Text(scope)) => {
+                    Some(scope)
+                }
+                _ => None,
+            })
+            .ok_or("token scope is missing")?;
+
+        Ok(Token {
+            subject: Principal::from_text(subject).map_err(format_error)?,
+            audience: Principal::from_text(audience).map_err(format_error)?,
+            scope,
+            expiration,
+        })
+    }
+
+    // `folders` is the target folder followed by its ancestors up to the root
+    pub fn allows(&self, permission: Permission, folders: &[u32]) -> bool {
+        self.scope.split_whitespace().any(|entry| {
+            let (action, folder) = match entry.split_once(':') {
+                Some((action, folder)) => (action, Some(folder)),
+                None => (entry, None),
+            };
+
+            let granted = match action {
+                "write" => true,
+                "read" => permission == Permission::Read,
+                _ => false,
+            };
+
+            granted
+                && match folder {
+                    None => true,
+                    Some(folder) => folder
+                        .parse::<u32>()
+                        .is_ok_and(|folder| folders.contains(&folder)),
+                }
+        })
+    }
+}
+
+fn ed25519_verify(pub_keys: &[ByteBuf], sig: &[u8], msg: &[u8]) -> Result<(), String> {
+    let sig = ed25519_dalek::Signature::from_slice(sig).map_err(format_error)?;
+    for key in pub_keys {
+        let Ok(key) = <[u8; 32]>::try_from(key.as_slice()) else {
+            continue;
+        };
+        if let Ok(key) = ed25519_dalek::VerifyingKey::from_bytes(&key) {
+            if key.verify_strict(msg, &sig).is_ok() {
+                return Ok(());
+            }
+        }
+    }
+    Err("token signature does not match a trusted key".to_string())
+}
+
+fn secp256k1_verify(pub_keys: &[ByteBuf], sig: &[u8], msg: &[u8]) -> Result<(), String> {
+    use k256::ecdsa::signature::Verifier;
+
+    let sig = k256::ecdsa::Signature::try_from(sig).map_err(format_error)?;
+    for key in pub_keys {
+        if let Ok(key) = k256::ecdsa::VerifyingKey::from_sec1_bytes(key) {
+            if key.verify(msg, &sig).is_ok() {
+                return Ok(());
+            }
+        }
+    }
+    Err("token signature does not match a trusted key".to_string())
+}
@@ -12,6 +12,7 @@ mod api_http;
 mod api_init;
 mod api_query;
 mod api_update;
+mod cose;
 mod store;
 
 mod types;
@@ -26,6 +27,8 @@ pub use bytes::*;
 pub const MAX_CHUNK_SIZE: u32 = 256 * 1024;
 pub const MAX_FILE_SIZE: u64 = 384 * 1024 * 1024 * 1024; // 384G
 pub const MAX_FILE_SIZE_PER_CALL: u64 = 1024 * 2000; // should less than 2M
+pub const DEFAULT_MAX_FILES_PER_OWNER: u32 = 1000;
+pub const DEFAULT_MAX_STORAGE_PER_OWNER: u64 = 1024 * 1024 * 1024; // 1G
 
 const MILLISECONDS: u64 = 1_000_000;
 
@@ -47,15 +50,67 @@ fn is_controller() -> Result<(), String> {
     }
 }
 
-fn is_controller_or_manager() -> Result<(), String> {
+
+fn is_controller_or_manager(caller: &Principal) -> bool {
+    ic_cdk::api::is_controller(caller) || store::state::is_manager(caller)
+}
+
+// controllers and managers can always read and write, auditors can read, anyone can read a
+// public bucket, everybody else needs an access token whose scope covers `folder`
+// and whose subject is the caller
+fn check_access(
+    access_token: Option<&ByteBuf>,
+    permission: cose::Permission,
+    folder: u32,
+) -> Result<(), String> {
+    verify_access(access_token, permission, folder, false)
+}
+
+// tokens in http urls are bearer tokens, they can only read and are not bound to the caller
+fn check_http_access(access_token: Option<&ByteBuf>, folder: u32) -> Result<(), String> {
+    verify_access(access_token, cose::Permission::Read, folder, true)
+}
+
+fn verify_access(
+    access_token: Option<&ByteBuf>,
+    permission: cose::Permission,
+    folder: u32,
+    bearer: bool,
+) -> Result<(), String> {
     let caller = ic_cdk::caller();
-    if ic_cdk::api::is_controller(&caller) || store::state::is_manager(&caller) {
-        Ok(())
-    } else {
-        Err("user is not a controller or manager".to_string())
+    if is_controller_or_manager(&caller) {
+        return Ok(());
+    }
+
+    let (public, auditor) =
+        store::state::with(|b| (b.visibility == 1, b.auditors.contains(&caller)));
+    if permission == cose::Permission::Read && (public || auditor) {
+        return Ok(());
     }
-}
 
+    let access_token = access_token.ok_or("access token required")?;
+    let now_sec = (ic_cdk::api::time() / 1_000_000_000) as i64;
+    let token = store::state::with(|b| {
+        cose::Token::from_sign1(
+            access_token,
+            &b.trusted_ecdsa_pub_keys,
+            &b.trusted_eddsa_pub_keys,
+            now_sec,
+        )
+    })?;
+
+    if token.audience != ic_cdk::id() {
+        return Err("token audience mismatch".to_string());
+    }
+    let bearer_read = bearer && permission == cose::Permission::Read;
+    if !bearer_read && token.subject != caller {
+        return Err("token subject mismatch".to_string());
+    }
+    if !token.allows(permission, &store::fs::folder_path(folder)) {
+        return Err(format!("token scope does not allow {:?} here", permission));
+    }
+    Ok(())
+}
 
 pub fn format_error<T>(err: T) -> String
 where
@@ -19,7 +19,10 @@ use serde::{Deserialize, Serialize};
 use serde_bytes::ByteBuf;
 use std::{borrow::Cow, cell::RefCell, collections::BTreeSet, ops};
 
-use crate::{types::FileInfo, MAX_CHUNK_SIZE};
+use crate::{
+    types::{FileInfo, FolderInfo},
+    MAX_CHUNK_SIZE,
+};
 
 type Memory = VirtualMemory<DefaultMemoryImpl>;
 
@@ -28,7 +31,14 @@ pub struct Bucket {
     pub name: String,
     pub file_count: u64,
     pub file_id: u32,
+    #[serde(default)]
+    pub folder_id: u32,
     pub max_file_size: u64,
+    // per owner limits, 0: unlimited
+    #[serde(default)]
+    pub max_files_per_owner: u32,
+    #[serde(default)]
+    pub max_storage_per_owner: u64,
     pub max_dir_depth: u8,
     pub max_children: u16,
     pub status: i8,     // -1: archived; 0: readable and writable; 1: readonly
@@ -91,6 +101,8 @@ pub struct FileMetadata {
     pub hash: Option<ByteBuf>, // [u8; 32]
     pub ert: Option<String>,   // External Resource Type
                                // ERT indicates that the file is an external resource. The content stored in the file includes a link to the external resource and other key information.
+    #[serde(default)]
+    pub owner: Option<Principal>, // none for files uploaded before owners were recorded
 }
 
 impl Storable for FileMetadata {
@@ -122,6 +134,7 @@ impl FileMetadata {
             status: self.status,
             hash: self.hash,
             ert: self.ert,
+            owner: self.owner,
         }
     }
 }
@@ -155,6 +168,25 @@ pub struct DirectoryMetadata {
     pub created_at: u64,      // unix timestamp in milliseconds
     pub updated_at: u64,      // unix timestamp in milliseconds
     pub status: i8,           // -1: archived; 0: readable and writable; 1: readonly
+    #[serde(default)]
+    pub owner: Option<Principal>, // none for folders created before owners were recorded
+}
+
+impl DirectoryMetadata {
+    pub fn into_info(self, id: u32) -> FolderInfo {
+        FolderInfo {
+            id,
+            parent: self.parent,
+            name: self.name,
+            ancestors: self.ancestors,
+            files: self.files.into_iter().collect(),
+            folders: self.directories.into_iter().collect(),
+            created_at: Nat::from(self.created_at),
+            updated_at: Nat::from(self.updated_at),
+            status: self.status,
+            owner: self.owner,
+        }
+    }
 }
 
 impl Storable for DirectoryMetadata {
@@ -171,6 +203,27 @@ impl Storable for DirectoryMetadata {
     }
 }
 
+// storage used by the files of one owner, `size` counts the filled bytes
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct Usage {
+    pub files: u64,
+    pub size: u64,
+}
+
+impl Storable for Usage {
+    const BOUND: Bound = Bound::Unbounded;
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut buf = vec![];
+        into_writer(self, &mut buf).expect("failed to encode Usage data");
+        Cow::Owned(buf)
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        from_reader(&bytes[..]).expect("failed to decode Usage data")
+    }
+}
+
 #[derive(Clone, Default, Deserialize, Serialize)]
 pub struct RootChildren {
     pub files: BTreeSet<u32>,
@@ -197,6 +250,7 @@ const DIR_METADATA_MEMORY_ID: MemoryId = MemoryId::new(2);
 const FS_METADATA_MEMORY_ID: MemoryId = MemoryId::new(3);
 const FS_DATA_MEMORY_ID: MemoryId = MemoryId::new(4);
 const HASH_INDEX_MEMORY_ID: MemoryId = MemoryId::new(5);
+const USAGE_MEMORY_ID: MemoryId = MemoryId::new(6);
 
 thread_local! {
     static HTTP_TREE: RefCell<HttpCertificationTree> = RefCell::new(HttpCertificationTree::default());
@@ -245,6 +299,12 @@ thread_local! {
             MEMORY_MANAGER.with_borrow(|m| m.get(HASH_INDEX_MEMORY_ID)),
         )
     );
+
+    static USAGE: RefCell<StableBTreeMap<Principal, Usage, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with_borrow(|m| m.get(USAGE_MEMORY_ID)),
+        )
+    );
 }
 
 pub mod state {
@@ -324,7 +384,10 @@ pub mod state {
 }
 
 pub mod fs {
-    use crate::{types::FileChunk, Bytes32, MAX_FILE_SIZE, MAX_FILE_SIZE_PER_CALL};
+    use crate::{
+        types::{valid_file_name, valid_file_parent, FileChunk},
+        Bytes32, MAX_FILE_SIZE, MAX_FILE_SIZE_PER_CALL,
+    };
 
     use super::*;
 
@@ -336,25 +399,215 @@ pub mod fs {
         FS_METADATA.with(|r| r.borrow().get(&id))
     }
 
+    pub fn get_usage(owner: &Principal) -> Usage {
+        USAGE.with(|r| r.borrow().get(owner)).unwrap_or_default()
+    }
+
+    fn update_usage(owner: Principal, f: impl FnOnce(&mut Usage)) {
+        USAGE.with(|r| {
+            let mut m = r.borrow_mut();
+            let mut usage = m.get(&owner).unwrap_or_default();
+            f(&mut usage);
+            if usage.files == 0 && usage.size == 0 {
+                m.remove(&owner);
+            } else {
+                m.insert(owner, usage);
+            }
+        });
+    }
+
+    pub fn get_folder(id: u32) -> Option<DirectoryMetadata> {
+        DIR_METADATA.with(|r| r.borrow().get(&id))
+    }
+
+    // the folder followed by its ancestors, ending with the root
+    pub fn folder_path(id: u32) -> Vec<u32> {
+        if id == 0 {
+            return vec![0];
+        }
+
+        match get_folder(id) {
+            Some(dir) => {
+                let mut path = Vec::with_capacity(dir.ancestors.len() + 1);
+                path.push(id);
+                path.extend(dir.ancestors);
+                path
+            }
+            None => vec![id],
+        }
+    }
+
+    // file and folder ids directly under `parent`, 0 is the root
+    fn children(parent: u32) -> Option<(BTreeSet<u32>, BTreeSet<u32>)> {
+        if parent == 0 {
+            return Some(ROOT_CHILDREN_HEAP.with(|r| {
+                let root = r.borrow();
+                (root.files.clone(), root.directories.clone())
+            }));
+        }
+
+        get_folder(parent).map(|dir| (dir.files, dir.directories))
+    }
+
+    fn update_children<R>(
+        parent: u32,
+        f: impl FnOnce(&mut BTreeSet<u32>, &mut BTreeSet<u32>) -> Result<R, String>,
+    ) -> Result<R, String> {
+        if parent == 0 {
+            return ROOT_CHILDREN_HEAP.with(|r| {
+                let mut root = r.borrow_mut();
+                let RootChildren { files, directories } = &mut *root;
+                f(files, directories)
+            });
+        }
+
+        DIR_METADATA.with(|r| {
+            let mut m = r.borrow_mut();
+            match m.get(&parent) {
+                None => Err(format!("folder not found: {}", parent)),
+                Some(mut dir) => {
+                    if dir.status > 0 {
+                        return Err("folder is readonly".to_string());
+                    }
+
+                    let res = f(&mut dir.files, &mut dir.directories)?;
+                    m.insert(parent, dir);
+                    Ok(res)
+                }
+            }
+        })
+    }
+
     pub fn list_files(parent: u32, prev: u32, take: u32) -> Vec<FileInfo> {
-        if parent != 0 {
+        let Some((files, _)) = children(parent) else {
             return Vec::new();
-        }
+        };
 
         FS_METADATA.with(|r| {
             let m = r.borrow();
-            let mut res = Vec::with_capacity(take as usize);
-            let mut id = prev.saturating_sub(1);
-            while id > 0 {
-                if let Some(meta) = m.get(&id) {
-                    res.push(meta.into_info(id));
-                    if res.len() >= take as usize {
-                        break;
-                    }
-                }
-                id = id.saturating_sub(1);
+            files
+                .range(..prev)
+                .rev()
+                .filter_map(|id| m.get(id).map(|meta| meta.into_info(*id)))
+                .take(take as usize)
+                .collect()
+        })
+    }
+
+    pub fn list_folders(parent: u32, prev: u32, take: u32) -> Vec<FolderInfo> {
+        let Some((_, directories)) = children(parent) else {
+            return Vec::new();
+        };
+
+        DIR_METADATA.with(|r| {
+            let m = r.borrow();
+            directories
+                .range(..prev)
+                .rev()
+                .filter_map(|id| m.get(id).map(|meta| meta.into_info(*id)))
+                .take(take as usize)
+                .collect()
+        })
+    }
+
+    // resolves a path like "/agents/abc" to a folder id, "/" is the root
+    pub fn find_folder(path: &str) -> Result<u32, String> {
+        if !valid_file_parent(path) {
+            return Err(format!("invalid folder path: {}", path));
+        }
+
+        let mut id = 0u32;
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            let (_, directories) =
+                children(id).ok_or_else(|| format!("folder not found: {}", id))?;
+            id = directories
+                .into_iter()
+                .find(|child| get_folder(*child).is_some_and(|dir| dir.name == name))
+                .ok_or_else(|| format!("folder not found: {}", path))?;
+        }
+        Ok(id)
+    }
+
+    // resolves a path like "/agents/abc/logo.png" to a file id, the newest file wins
+    // when a folder holds several files with the same name
+    pub fn find_file(path: &str) -> Result<u32, String> {
+        let (parent, name) = path
+            .rsplit_once('/')
+            .ok_or_else(|| format!("invalid file path: {}", path))?;
+        if !valid_file_name(name) {
+            return Err(format!("invalid file path: {}", path));
+        }
+
+        let parent = find_folder(if parent.is_empty() { "/" } else { parent })?;
+        let (files, _) = children(parent).ok_or_else(|| format!("folder not found: {}", parent))?;
+        files
+            .into_iter()
+            .rev()
+            .find(|id| get_file(*id).is_some_and(|meta| meta.name == name))
+            .ok_or_else(|| format!("file not found: {}", path))
+    }
+
+    pub fn add_folder(
+        parent: u32,
+        name: String,
+        owner: Principal,
+        now_ms: u64,
+    ) -> Result<u32, String> {
+        state::with_mut(|s| {
+            let id = s.folder_id.saturating_add(1);
+            if id == u32::MAX {
+                return Err("folder id overflow".to_string());
             }
-            res
+
+            // ancestors run from the parent up to the root
+            let ancestors = if parent == 0 {
+                vec![0]
+            } else {
+                let dir =
+                    get_folder(parent).ok_or_else(|| format!("folder not found: {}", parent))?;
+                let mut ancestors = Vec::with_capacity(dir.ancestors.len() + 1);
+                ancestors.push(parent);
+                ancestors.extend(dir.ancestors);
+                ancestors
+            };
+            if ancestors.len() > s.max_dir_depth as usize {
+                return Err(format!("folder depth exceeds limit: {}", s.max_dir_depth));
+            }
+
+            let (_, siblings) =
+                children(parent).ok_or_else(|| format!("folder not found: {}", parent))?;
+            if siblings
+                .into_iter()
+                .any(|child| get_folder(child).is_some_and(|dir| dir.name == name))
+            {
+                return Err(format!("folder already exists: {}", name));
+            }
+
+            let max_children = s.max_children as usize;
+            update_children(parent, |_, directories| {
+                if directories.len() >= max_children {
+                    return Err(format!("children exceed limit: {}", max_children));
+                }
+                directories.insert(id);
+                Ok(())
+            })?;
+
+            s.folder_id = id;
+            DIR_METADATA.with(|r| {
+                r.borrow_mut().insert(
+                    id,
+                    DirectoryMetadata {
+                        parent,
+                        name,
+                        ancestors,
+                        created_at: now_ms,
+                        updated_at: now_ms,
+                        owner: Some(owner),
+                        ..Default::default()
+                    },
+                )
+            });
+            Ok(id)
         })
     }
 
@@ -365,26 +618,81 @@ pub mod fs {
                 return Err("file id overflow".to_string());
             }
 
-            if let Some(ref hash) = meta.hash {
-                let hash: Bytes32 = hash.try_into()?;
-                HASH_INDEX.with(|r| {
-                    let mut m = r.borrow_mut();
-                    if let Some(prev) = m.get(&hash) {
-                        return Err(format!("file hash conflict, {}", prev));
-                    }
+            let hash: Option<Bytes32> = match meta.hash {
+                Some(ref hash) => Some(hash.try_into()?),
+                None => None,
+            };
+            if let Some(ref hash) = hash {
+                if let Some(prev) = HASH_INDEX.with(|r| r.borrow().get(&hash.0)) {
+                    return Err(format!("file hash conflict, {}", prev));
+                }
+            }
 
-                    m.insert(hash.0, id);
-                    Ok(())
-                })?;
+            if let Some(owner) = meta.owner {
+                let usage = get_usage(&owner);
+                if s.max_files_per_owner > 0 && usage.files >= s.max_files_per_owner as u64 {
+                    return Err(format!("file count quota exceeded: {}", s.max_files_per_owner));
+                }
             }
 
+            let max_children = s.max_children as usize;
+            update_children(meta.parent, |files, _| {
+                if files.len() >= max_children {
+                    return Err(format!("children exceed limit: {}", max_children));
+                }
+                files.insert(id);
+                Ok(())
+            })?;
+
+            if let Some(hash) = hash {
+                HASH_INDEX.with(|r| r.borrow_mut().insert(hash.0, id));
+            }
+            if let Some(owner) = meta.owner {
+                update_usage(owner, |usage| usage.files += 1);
+            }
             s.file_id = id;
-            ROOT_CHILDREN_HEAP.with(|r| r.borrow_mut().files.insert(id));
             FS_METADATA.with(|r| r.borrow_mut().insert(id, meta));
             Ok(id)
         })
     }
 
+    pub fn move_file(id: u32, to: u32, now_ms: u64) -> Result<(), String> {
+        let metadata = get_file(id).ok_or_else(|| format!("file not found: {}", id))?;
+        if metadata.status > 0 {
+            return Err("file is readonly".to_string());
+        }
+        if metadata.parent == to {
+            return Ok(());
+        }
+
+        let max_children = state::with(|s| s.max_children) as usize;
+        update_children(to, |files, _| {
+            if files.len() >= max_children {
+                return Err(format!("children exceed limit: {}", max_children));
+            }
+            files.insert(id);
+            Ok(())
+        })?;
+        if let Err(err) = update_children(metadata.parent, |files, _| {
+            files.remove(&id);
+            Ok(())
+        }) {
+            let _ = update_children(to, |files, _| {
+                files.remove(&id);
+                Ok(())
+            });
+            return Err(err);
+        }
+
+        FS_METADATA.with(|r| {
+            let mut metadata = metadata;
+            metadata.parent = to;
+            metadata.updated_at = now_ms;
+            r.borrow_mut().insert(id, metadata)
+        });
+        Ok(())
+    }
+
     pub fn update_file<R>(id: u32, f: impl FnOnce(&mut FileMetadata) -> R) -> Result<R, String> {
         FS_METADATA.with(|r| {
             let mut m = r.borrow_mut();
@@ -516,6 +824,7 @@ pub mod fs {
         }
 
         let max = state::max_file_size();
+        let max_storage = state::with(|s| s.max_storage_per_owner);
         FS_METADATA.with(|r| {
             let mut m = r.borrow_mut();
             match m.get(&file_id) {
@@ -525,26 +834,38 @@ pub mod fs {
                         return Err("file is readonly".to_string());
                     }
 
-                    metadata.updated_at = now_ms;
-                    metadata.filled += chunk.len() as u64;
-                    if metadata.filled > max {
+                    let prev_filled = metadata.filled;
+
+                    // a chunk that is written again replaces the old one
+                    let replaced = FS_DATA.with(|r| {
+                        r.borrow()
+                            .get(&FileId(file_id, chunk_index))
+                            .map(|old| old.0.len() as u64)
+                    });
+                    let new_filled =
+                        prev_filled + chunk.len() as u64 - replaced.unwrap_or_default();
+                    if new_filled > max {
                         panic!("file size exceeds limit: {}", max);
                     }
+                    if let Some(owner) = metadata.owner {
+                        let usage = get_usage(&owner);
+                        let size = (usage.size + new_filled).saturating_sub(prev_filled);
+                        if max_storage > 0 && new_filled > prev_filled && size > max_storage {
+                            return Err(format!("storage quota exceeded: {}", max_storage));
+                        }
+                        update_usage(owner, |usage| usage.size = size);
+                    }
 
-                    match FS_DATA.with(|r| {
+                    let inserted = FS_DATA.with(|r| {
                         r.borrow_mut()
                             .insert(FileId(file_id, chunk_index), Chunk(chunk))
-                    }) {
-                        None => {
-                            if metadata.chunks <= chunk_index {
-                                metadata.chunks = chunk_index + 1;
-                            }
-                        }
-                        Some(old) => {
-                            metadata.filled -= old.0.len() as u64;
-                        }
+                    });
+                    if inserted.is_none() && metadata.chunks <= chunk_index {
+                        metadata.chunks = chunk_index + 1;
                     }
 
+                    metadata.updated_at = now_ms;
+                    metadata.filled = new_filled;
                     let filled = metadata.filled;
                     if metadata.size < filled {
                         metadata.size = filled;
@@ -558,20 +879,26 @@ pub mod fs {
     }
 
     pub fn delete_file(id: u32) -> Result<(), String> {
-        let metadata = FS_METADATA.with(|r| {
-            let mut m = r.borrow_mut();
-            if let Some(metadata) = m.get(&id) {
-                if metadata.status > 0 {
-                    return Err("file is readonly".to_string());
-                }
-                m.remove(&id);
-                Ok(metadata)
-            } else {
-                Err(format!("file not found: {}", id))
-            }
+        let metadata = get_file(id).ok_or_else(|| format!("file not found: {}", id))?;
+        if metadata.status > 0 {
+            return Err("file is readonly".to_string());
+        }
+
+        update_children(metadata.parent, |files, _| {
+            files.remove(&id);
+            Ok(())
         })?;
+        remove_file_data(id, metadata)
+    }
 
-        ROOT_CHILDREN_HEAP.with(|r| r.borrow_mut().files.remove(&id));
+    fn remove_file_data(id: u32, metadata: FileMetadata) -> Result<(), String> {
+        FS_METADATA.with(|r| r.borrow_mut().remove(&id));
+        if let Some(owner) = metadata.owner {
+            update_usage(owner, |usage| {
+                usage.files = usage.files.saturating_sub(1);
+                usage.size = usage.size.saturating_sub(metadata.filled);
+            });
+        }
         if let Some(hash) = metadata.hash {
             let hash: Bytes32 = hash.try_into()?;
             HASH_INDEX.with(|r| r.borrow_mut().remove(&hash));
@@ -583,6 +910,58 @@ pub mod fs {
         });
         Ok(())
     }
+
+    // a recursive delete removes every file and folder below `id`, it fails before
+    // touching anything when one of them is readonly or, with `owner` set, owned by someone else
+    pub fn delete_folder(id: u32, recursive: bool, owner: Option<Principal>) -> Result<(), String> {
+        if id == 0 {
+            return Err("root folder cannot be deleted".to_string());
+        }
+
+        let folder = get_folder(id).ok_or_else(|| format!("folder not found: {}", id))?;
+        if !recursive && !(folder.files.is_empty() && folder.directories.is_empty()) {
+            return Err("folder is not empty".to_string());
+        }
+
+        let mut folders = vec![id];
+        let mut files: Vec<(u32, FileMetadata)> = Vec::new();
+        let mut next = 0;
+        while next < folders.len() {
+            let dir = get_folder(folders[next])
+                .ok_or_else(|| format!("folder not found: {}", folders[next]))?;
+            if dir.status > 0 {
+                return Err(format!("folder is readonly: {}", folders[next]));
+            }
+
+            for file in dir.files {
+                let metadata = get_file(file).ok_or_else(|| format!("file not found: {}", file))?;
+                if metadata.status > 0 {
+                    return Err(format!("file is readonly: {}", file));
+                }
+                if owner.is_some() && metadata.owner != owner {
+                    return Err(format!("file is owned by someone else: {}", file));
+                }
+                files.push((file, metadata));
+            }
+            folders.extend(dir.directories);
+            next += 1;
+        }
+
+        update_children(folder.parent, |_, directories| {
+            directories.remove(&id);
+            Ok(())
+        })?;
+        for (file, metadata) in files {
+            remove_file_data(file, metadata)?;
+        }
+        DIR_METADATA.with(|r| {
+            let mut m = r.borrow_mut();
+            for dir in folders {
+                m.remove(&dir);
+            }
+        });
+        Ok(())
+    }
 }
 
 // #[cfg(test)]
@@ -1,5 +1,5 @@
 use base64::{engine::general_purpose, Engine};
-use candid::{CandidType, Nat};
+use candid::{CandidType, Nat, Principal};
 use serde::{Deserialize, Serialize};
 use serde_bytes::ByteBuf;
 use std::path::Path;
@@ -22,11 +22,41 @@ pub struct FileInfo {
     pub hash: Option<ByteBuf>,
     pub ert: Option<String>, // External Resource Type
                              // ERT indicates that the file is an external resource. The content stored in the file includes a link to the external resource and other key information.
+    pub owner: Option<Principal>,
+}
+
+#[derive(CandidType, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct FolderInfo {
+    pub id: u32,
+    pub parent: u32, // 0: root
+    pub name: String,
+    pub ancestors: Vec<u32>, // parent, [parent's upper layer, ...], root
+    pub files: Vec<u32>,
+    pub folders: Vec<u32>,
+    pub created_at: Nat, // unix timestamp in milliseconds
+    pub updated_at: Nat, // unix timestamp in milliseconds
+    pub status: i8,      // -1: archived; 0: readable and writable; 1: readonly
+    pub owner: Option<Principal>,
+}
+
+#[derive(CandidType, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CreateFolderInput {
+    pub parent: u32,
+    pub name: String,
+}
+
+impl CreateFolderInput {
+    pub fn validate(&self) -> Result<(), String> {
+        if !valid_file_name(&self.name) {
+            return Err("invalid folder name".to_string());
+        }
+        Ok(())
+    }
 }
 
 #[derive(CandidType, Clone, Debug, Default, Deserialize, Serialize)]
 pub struct CreateFileInput {
-    // pub parent: u32,
+    pub parent: Option<u32>, // 0 or none: root
     pub name: String,
     pub content_type: String,
     pub size: Option<Nat>, // if provided, can be used to detect the file is fully filled
@@ -36,6 +66,8 @@ pub struct CreateFileInput {
     pub hash: Option<ByteBuf>, // recommend sha3 256
     pub ert: Option<String>,
     pub crc32: Option<u32>,
+    // logical owner when a manager uploads on behalf of a user, defaults to the caller
+    pub owner: Option<Principal>,
 }
 
 
@@ -48,22 +80,22 @@ pub fn valid_file_name(name: &str) -> bool {
     p.file_name() == Some(p.as_os_str())
 }
 
-// pub fn valid_file_parent(parent: &str) -> bool {
-//     if parent.is_empty() || parent == "/" {
-//         return true;
-//     }
+pub fn valid_file_parent(parent: &str) -> bool {
+    if parent.is_empty() || parent == "/" {
+        return true;
+    }
 
-//     if !parent.starts_with('/') {
-//         return false;
-//     }
+    if !parent.starts_with('/') {
+        return false;
+    }
 
-//     for name in parent[1..].split('/') {
-//         if !valid_file_name(name) {
-//             return false;
-//         }
-//     }
-//     true
-// }
+    for name in parent[1..].split('/') {
+        if !valid_file_name(name) {
+            return false;
+        }
+    }
+    true
+}
 
 impl CreateFileInput {
     pub fn validate(&self) -> Result<(), String> {
@@ -169,6 +201,46 @@ pub struct UpdateFileChunkOutput {
 #[derive(CandidType, Clone, Debug, Default, Deserialize, Serialize)]
 pub struct FileChunk(pub u32, pub ByteBuf);
 
+#[derive(CandidType, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct UpdateBucketInput {
+    pub visibility: Option<u8>, // 0: private; 1: public
+    pub trusted_ecdsa_pub_keys: Option<Vec<ByteBuf>>,
+    pub trusted_eddsa_pub_keys: Option<Vec<ByteBuf>>,
+    pub max_files_per_owner: Option<u32>,   // 0: unlimited
+    pub max_storage_per_owner: Option<u64>, // 0: unlimited
+}
+
+#[derive(CandidType, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct UsageInfo {
+    pub owner: Principal,
+    pub file_count: u64,
+    pub storage_used: Nat,
+    pub max_files: u32,   // 0: unlimited
+    pub max_storage: Nat, // 0: unlimited
+}
+
+impl UpdateBucketInput {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(visibility) = self.visibility {
+            if visibility > 1 {
+                return Err("visibility should be 0 or 1".to_string());
+            }
+        }
+        if let Some(keys) = &self.trusted_ecdsa_pub_keys {
+            // SEC1 encoded, compressed or uncompressed
+            if keys.iter().any(|key| key.len() != 33 && key.len() != 65) {
+                return Err("trusted_ecdsa_pub_keys must be 33 or 65 bytes".to_string());
+            }
+        }
+        if let Some(keys) = &self.trusted_eddsa_pub_keys {
+            if keys.iter().any(|key| key.len() != 32) {
+                return Err("trusted_eddsa_pub_keys must be 32 bytes".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
 pub struct UrlFileParam {
     pub file: u32,
     pub hash: Option<Bytes32>,